@@ -8,15 +8,30 @@ pub use self::{
     types::*,
 };
 
-use futures::{channel::mpsc, prelude::*};
+use chashmap::CHashMap;
+use futures::{
+    channel::mpsc,
+    future::{AbortHandle, Abortable},
+    prelude::*,
+};
 use log::error;
+use serde::Deserialize;
 use std::sync::Arc;
 
+const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+
+#[derive(Debug, Deserialize)]
+struct CancelParams {
+    id: Id,
+}
+
 #[derive(Debug)]
 pub struct MessageHandler<S, C> {
     pub server: Arc<S>,
     pub client: Arc<C>,
     pub output: mpsc::Sender<String>,
+    pending_requests: Arc<CHashMap<Id, AbortHandle>>,
+    after_message_tx: mpsc::UnboundedSender<()>,
 }
 
 impl<S, C> MessageHandler<S, C>
@@ -24,6 +39,26 @@ where
     S: RequestHandler + Middleware + Send + Sync + 'static,
     C: ResponseHandler + Send + Sync + 'static,
 {
+    pub fn new(server: Arc<S>, client: Arc<C>, output: mpsc::Sender<String>) -> Self {
+        // The messages are post-processed one after another in the order in which they were handled.
+        let (after_message_tx, mut after_message_rx) = mpsc::unbounded();
+        let after_message_server = Arc::clone(&server);
+        tokio::spawn(async move {
+            while let Some(()) = after_message_rx.next().await {
+                while let Ok(Some(())) = after_message_rx.try_next() {}
+                Arc::clone(&after_message_server).after_message().await;
+            }
+        });
+
+        Self {
+            server,
+            client,
+            output,
+            pending_requests: Arc::new(CHashMap::new()),
+            after_message_tx,
+        }
+    }
+
     pub async fn handle(&mut self, json: &str) {
         self.server.before_message().await;
 
@@ -31,17 +66,33 @@ where
             Ok(Message::Request(request)) => {
                 let server = Arc::clone(&self.server);
                 let mut output = self.output.clone();
+                let pending_requests = Arc::clone(&self.pending_requests);
+                let after_message_tx = self.after_message_tx.clone();
+                let (abort_handle, abort_registration) = AbortHandle::new_pair();
+                pending_requests.insert(request.id.clone(), abort_handle);
                 tokio::spawn(async move {
-                    let response = server.handle_request(request).await;
+                    let id = request.id.clone();
+                    let response =
+                        match Abortable::new(server.handle_request(request), abort_registration)
+                            .await
+                        {
+                            Ok(response) => response,
+                            Err(_) => Response::error(Error::request_cancelled(), Some(id.clone())),
+                        };
+                    pending_requests.remove(&id);
+
                     if let Some(error) = response.error.as_ref() {
                         error!("{:?}", error);
                     }
                     let json = serde_json::to_string(&response).unwrap();
                     output.send(json).await.unwrap();
-                    server.after_message().await;
+                    let _ = after_message_tx.unbounded_send(());
                 });
             }
             Ok(Message::Notification(notification)) => {
+                if notification.method == CANCEL_REQUEST_METHOD {
+                    self.cancel_request(&notification);
+                }
                 self.server.handle_notification(notification).await;
                 self.after_message();
             }
@@ -58,10 +109,123 @@ where
         };
     }
 
+    fn cancel_request(&self, notification: &Notification) {
+        if let Ok(params) = serde_json::from_value::<CancelParams>(notification.params.clone()) {
+            if let Some(abort_handle) = self.pending_requests.remove(&params.id) {
+                abort_handle.abort();
+            }
+        }
+    }
+
     fn after_message(&self) {
-        let server = Arc::clone(&self.server);
-        tokio::spawn(async move {
-            server.after_message().await;
-        });
+        let _ = self.after_message_tx.unbounded_send(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    struct PendingServer;
+
+    #[async_trait]
+    impl RequestHandler for PendingServer {
+        async fn handle_request(&self, _request: Request) -> Response {
+            future::pending().await
+        }
+
+        async fn handle_notification(&self, _notification: Notification) {}
+    }
+
+    #[async_trait]
+    impl Middleware for PendingServer {
+        async fn before_message(&self) {}
+
+        async fn after_message(self: Arc<Self>) {}
+    }
+
+    #[derive(Default)]
+    struct OrderedServer {
+        is_running: AtomicBool,
+        has_overlapped: AtomicBool,
+        finished: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl RequestHandler for OrderedServer {
+        async fn handle_request(&self, request: Request) -> Response {
+            Response::result(json!(null), request.id)
+        }
+
+        async fn handle_notification(&self, _notification: Notification) {}
+    }
+
+    #[async_trait]
+    impl Middleware for OrderedServer {
+        async fn before_message(&self) {}
+
+        async fn after_message(self: Arc<Self>) {
+            if self.is_running.swap(true, Ordering::SeqCst) {
+                self.has_overlapped.store(true, Ordering::SeqCst);
+            }
+            for _ in 0..10 {
+                let _ = tokio::task::yield_now().await;
+            }
+            self.is_running.store(false, Ordering::SeqCst);
+            self.finished.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct NullClient;
+
+    #[async_trait]
+    impl ResponseHandler for NullClient {
+        async fn handle(&self, _response: Response) {}
+    }
+
+    #[tokio::test]
+    async fn cancel_request() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut handler = MessageHandler::new(Arc::new(PendingServer), Arc::new(NullClient), tx);
+
+        let request = Request::new("foo".into(), json!(null), Id::Number(1));
+        handler
+            .handle(&serde_json::to_string(&request).unwrap())
+            .await;
+
+        let notification = Notification::new(CANCEL_REQUEST_METHOD.into(), json!({ "id": 1 }));
+        handler
+            .handle(&serde_json::to_string(&notification).unwrap())
+            .await;
+
+        let response: Response = serde_json::from_str(&rx.next().await.unwrap()).unwrap();
+        assert_eq!(
+            response,
+            Response::error(Error::request_cancelled(), Some(Id::Number(1)))
+        );
+    }
+
+    #[tokio::test]
+    async fn after_message_in_order() {
+        let (tx, _rx) = mpsc::channel(1);
+        let server = Arc::new(OrderedServer::default());
+        let mut handler = MessageHandler::new(Arc::clone(&server), Arc::new(NullClient), tx);
+
+        for _ in 0..3 {
+            let notification = Notification::new("foo".into(), json!(null));
+            handler
+                .handle(&serde_json::to_string(&notification).unwrap())
+                .await;
+        }
+        drop(handler);
+
+        while Arc::strong_count(&server) > 1 {
+            let _ = tokio::task::yield_now().await;
+        }
+        assert!(server.finished.load(Ordering::SeqCst) > 0);
+        assert!(!server.has_overlapped.load(Ordering::SeqCst));
     }
 }
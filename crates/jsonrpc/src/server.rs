@@ -3,6 +3,7 @@ use async_trait::async_trait;
 use futures::prelude::*;
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json::json;
+use std::sync::Arc;
 
 pub type Result<T> = std::result::Result<T, String>;
 
@@ -17,7 +18,7 @@ pub trait RequestHandler {
 pub trait Middleware {
     async fn before_message(&self);
 
-    async fn after_message(&self);
+    async fn after_message(self: Arc<Self>);
 }
 
 pub async fn handle_request<'a, H, F, I, O>(request: Request, handler: H) -> Response
@@ -64,6 +64,14 @@ impl Error {
             data: None,
         }
     }
+
+    pub fn request_cancelled() -> Self {
+        Self {
+            code: ErrorCode::RequestCancelled,
+            message: "Request cancelled".to_owned(),
+            data: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
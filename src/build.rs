@@ -4,7 +4,7 @@ use crate::{
         BuildParams, BuildResult, BuildStatus, ClientCapabilitiesExt, LatexOptions,
         LogMessageParams, LspClient, MessageType, ProgressParams, ProgressParamsValue,
         ProgressToken, Uri, WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressCreateParams,
-        WorkDoneProgressEnd,
+        WorkDoneProgressEnd, WorkDoneProgressReport,
     },
 };
 use async_trait::async_trait;
@@ -16,6 +16,8 @@ use futures::{
     stream,
 };
 use log::error;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use std::{collections::HashMap, io, path::Path, process::Stdio, sync::Arc};
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
@@ -27,6 +29,7 @@ pub struct BuildProvider<C> {
     client: Arc<C>,
     handles_by_token: Mutex<HashMap<ProgressToken, AbortHandle>>,
     current_docs: CHashMap<Uri, ()>,
+    pages_by_uri: CHashMap<Uri, u64>,
 }
 
 impl<C> BuildProvider<C> {
@@ -35,6 +38,7 @@ impl<C> BuildProvider<C> {
             client,
             handles_by_token: Mutex::new(HashMap::new()),
             current_docs: CHashMap::new(),
+            pages_by_uri: CHashMap::new(),
         }
     }
 
@@ -72,7 +76,7 @@ where
 
         let doc = req
             .snapshot()
-            .parent(&req.current().uri, &req.options, &req.current_dir)
+            .parent(&req.current().uri)
             .unwrap_or_else(|| Arc::clone(&req.view.current));
 
         if !doc.is_file() {
@@ -89,9 +93,10 @@ where
         }
         self.current_docs.insert(doc.uri.clone(), ());
 
+        let expected_pages = self.pages_by_uri.get(&doc.uri).map(|pages| *pages);
         let status = match doc.uri.to_file_path() {
             Ok(path) => {
-                if req.client_capabilities.has_work_done_progress_support() {
+                let progress_token = if req.client_capabilities.has_work_done_progress_support() {
                     let params = WorkDoneProgressCreateParams {
                         token: token.clone(),
                     };
@@ -105,18 +110,37 @@ where
                                 title,
                                 cancellable: Some(true),
                                 message: Some("Building".into()),
-                                percentage: None,
+                                percentage: expected_pages.map(|_| 0.0),
                             },
                         )),
                     };
                     self.client.progress(params).await;
-                }
+                    Some(token.clone())
+                } else {
+                    None
+                };
 
                 let latex_options = req.options.latex.clone().unwrap_or_default();
                 let client = Arc::clone(&self.client);
-                match Abortable::new(build(&path, &latex_options, client), reg).await {
-                    Ok(Ok(true)) => BuildStatus::Success,
-                    Ok(Ok(false)) => BuildStatus::Error,
+                let future = build(
+                    &path,
+                    &latex_options,
+                    client,
+                    progress_token,
+                    expected_pages,
+                );
+                match Abortable::new(future, reg).await {
+                    Ok(Ok(output)) => {
+                        if output.pages > 0 {
+                            self.pages_by_uri.insert(doc.uri.clone(), output.pages);
+                        }
+
+                        if output.success {
+                            BuildStatus::Success
+                        } else {
+                            BuildStatus::Error
+                        }
+                    }
                     Ok(Err(why)) => {
                         error!("Unable to build the document {}: {}", doc.uri, why);
                         BuildStatus::Failure
@@ -149,7 +173,19 @@ where
     }
 }
 
-async fn build<C>(path: &Path, options: &LatexOptions, client: Arc<C>) -> io::Result<bool>
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct BuildOutput {
+    success: bool,
+    pages: u64,
+}
+
+async fn build<C>(
+    path: &Path,
+    options: &LatexOptions,
+    client: Arc<C>,
+    progress_token: Option<ProgressToken>,
+    expected_pages: Option<u64>,
+) -> io::Result<BuildOutput>
 where
     C: LspClient + Send + Sync + 'static,
 {
@@ -180,8 +216,23 @@ where
     let stderr = BufReader::new(process.stderr.take().unwrap()).lines();
     let mut output = stream::select(stdout, stderr);
 
-    tokio::spawn(async move {
+    // The output is read on the same task, so that no progress is reported
+    // after the build has been cancelled.
+    let reader = async move {
+        let mut progress = BuildProgress::new(expected_pages);
         while let Some(Ok(line)) = output.next().await {
+            if progress.update(&line) {
+                if let Some(token) = &progress_token {
+                    let params = ProgressParams {
+                        token: token.clone(),
+                        value: ProgressParamsValue::WorkDone(WorkDoneProgress::Report(
+                            progress.report(),
+                        )),
+                    };
+                    client.progress(params).await;
+                }
+            }
+
             let params = LogMessageParams {
                 typ: MessageType::Log,
                 message: line,
@@ -189,9 +240,124 @@ where
 
             client.log_message(params).await;
         }
-    });
+        progress.page
+    };
 
-    Ok(process.await?.success())
+    let (status, pages) = future::join(process, reader).await;
+    let success = status?.success();
+    Ok(BuildOutput { success, pages })
+}
+
+static RUNNING_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("^Running '(?P<program>[^\\s']+)").unwrap());
+
+static PAGE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\\[(?P<page>\\d+)\\b").unwrap());
+
+static OUTPUT_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new("^Output written on .* \\((?P<pages>\\d+) pages?").unwrap());
+
+static RERUN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("(?P<msg>Rerun to get [^.]*)").unwrap());
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct BuildProgress {
+    pass: u64,
+    program: Option<String>,
+    page: u64,
+    expected_pages: Option<u64>,
+    rerun_message: Option<String>,
+    is_rerun_pending: bool,
+}
+
+impl BuildProgress {
+    fn new(expected_pages: Option<u64>) -> Self {
+        Self {
+            expected_pages,
+            ..Self::default()
+        }
+    }
+
+    fn update(&mut self, line: &str) -> bool {
+        if let Some(captures) = RUNNING_REGEX.captures(line) {
+            let program = Path::new(&captures["program"])
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned());
+
+            self.start_pass(program);
+            return true;
+        }
+
+        if let Some(captures) = OUTPUT_REGEX.captures(line) {
+            self.page = captures["pages"].parse().unwrap_or(self.page);
+            return true;
+        }
+
+        if let Some(captures) = RERUN_REGEX.captures(line) {
+            self.rerun_message = Some(captures["msg"].to_owned());
+            self.is_rerun_pending = true;
+            return true;
+        }
+
+        let last_page = PAGE_REGEX
+            .captures_iter(line)
+            .filter_map(|captures| captures["page"].parse().ok())
+            .max();
+
+        // Some tools rerun the engine without announcing the next pass.
+        if last_page.is_some() && self.is_rerun_pending {
+            self.pass = self.pass.max(1);
+            self.start_pass(self.program.clone());
+        }
+
+        match last_page {
+            Some(page) if page > self.page => {
+                self.page = page;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn start_pass(&mut self, program: Option<String>) {
+        self.pass += 1;
+        self.program = program;
+        self.page = 0;
+        self.rerun_message = None;
+        self.is_rerun_pending = false;
+    }
+
+    /// The number of passes is not known in advance,
+    /// so every pass covers half of the remaining progress.
+    fn percentage(&self) -> Option<f64> {
+        let expected_pages = self.expected_pages.filter(|pages| *pages > 0)?;
+        let pass_progress = (self.page as f64 / expected_pages as f64).min(1.0);
+        let remaining = 0.5_f64.powi(self.pass.max(1) as i32 - 1);
+        let percentage = 100.0 * (1.0 - remaining * (1.0 - pass_progress / 2.0));
+        Some(percentage.min(99.0))
+    }
+
+    fn message(&self) -> String {
+        let mut message = format!("Pass {}", self.pass.max(1));
+        if let Some(program) = &self.program {
+            message.push_str(&format!(" ({})", program));
+        }
+
+        if self.page > 0 {
+            message.push_str(&format!(": page {}", self.page));
+        }
+
+        if let Some(rerun_message) = &self.rerun_message {
+            message.push_str(&format!(", {}", rerun_message));
+        }
+        message
+    }
+
+    fn report(&self) -> WorkDoneProgressReport {
+        WorkDoneProgressReport {
+            cancellable: Some(true),
+            message: Some(self.message()),
+            percentage: self.percentage(),
+        }
+    }
 }
 
 fn replace_placeholder(arg: String, file: &Path) -> String {
@@ -201,3 +367,98 @@ fn replace_placeholder(arg: String, file: &Path) -> String {
         arg.replace("%f", &file.to_string_lossy())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(expected_pages: Option<u64>, lines: &[&str]) -> BuildProgress {
+        let mut progress = BuildProgress::new(expected_pages);
+        for line in lines {
+            progress.update(line);
+        }
+        progress
+    }
+
+    #[test]
+    fn page_counter() {
+        let progress = run(
+            None,
+            &[
+                "[1{/usr/share/texmf/fonts/map/pdftex/updmap/pdftex.map}] [2]",
+                "[3] [4]",
+            ],
+        );
+        assert_eq!(progress.page, 4);
+        assert_eq!(progress.message(), "Pass 1: page 4");
+        assert_eq!(progress.percentage(), None);
+    }
+
+    #[test]
+    fn page_counter_ignore_lengths() {
+        let progress = run(None, &["\\includegraphics[10mm]{foo}"]);
+        assert_eq!(progress.page, 0);
+    }
+
+    #[test]
+    fn latexmk_passes() {
+        let progress = run(
+            Some(8),
+            &[
+                "Running 'pdflatex  -interaction=nonstopmode -synctex=1  -recorder  \"main.tex\"'",
+                "[1] [2] [3] [4]",
+                "LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.",
+                "Running '/usr/bin/pdflatex  -interaction=nonstopmode  \"main.tex\"'",
+                "[1] [2]",
+            ],
+        );
+        assert_eq!(progress.pass, 2);
+        assert_eq!(progress.page, 2);
+        assert_eq!(progress.message(), "Pass 2 (pdflatex): page 2");
+        assert_eq!(progress.percentage(), Some(56.25));
+    }
+
+    #[test]
+    fn rerun_without_running() {
+        let progress = run(
+            Some(8),
+            &[
+                "[1] [2] [3] [4]",
+                "LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.",
+                "[1]",
+            ],
+        );
+        assert_eq!(progress.pass, 2);
+        assert_eq!(progress.page, 1);
+        assert_eq!(progress.message(), "Pass 2: page 1");
+    }
+
+    #[test]
+    fn rerun_message() {
+        let progress = run(
+            None,
+            &[
+                "Running 'pdflatex  \"main.tex\"'",
+                "[1] [2]",
+                "LaTeX Warning: Label(s) may have changed. Rerun to get cross-references right.",
+            ],
+        );
+        assert_eq!(
+            progress.message(),
+            "Pass 1 (pdflatex): page 2, Rerun to get cross-references right"
+        );
+    }
+
+    #[test]
+    fn output_written() {
+        let progress = run(
+            Some(10),
+            &[
+                "[1] [2]",
+                "Output written on main.pdf (12 pages, 123456 bytes).",
+            ],
+        );
+        assert_eq!(progress.page, 12);
+        assert_eq!(progress.percentage(), Some(50.0));
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    output::OutputLocations,
+    protocol::{Options, Uri},
+    workspace::Snapshot,
+};
+use log::warn;
+use std::{collections::HashSet, path::Path};
+use tokio::fs;
+
+const AUXILIARY_EXTENSIONS: &[&str] = &[
+    "aux",
+    "bbl",
+    "bcf",
+    "blg",
+    "fdb_latexmk",
+    "fls",
+    "glg",
+    "glo",
+    "gls",
+    "idx",
+    "ilg",
+    "ind",
+    "ist",
+    "lof",
+    "log",
+    "lot",
+    "nav",
+    "out",
+    "run.xml",
+    "snm",
+    "synctex",
+    "synctex.gz",
+    "synctex(busy)",
+    "toc",
+    "vrb",
+    "xdv",
+];
+
+const ARTIFACT_EXTENSIONS: &[&str] = &["dvi", "pdf", "ps"];
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CleanTarget {
+    Auxiliary,
+    Artifacts,
+}
+
+impl CleanTarget {
+    pub const ALL: &'static [Self] = &[Self::Auxiliary, Self::Artifacts];
+
+    pub fn command(self) -> &'static str {
+        match self {
+            Self::Auxiliary => "texlab.cleanAuxiliary",
+            Self::Artifacts => "texlab.cleanArtifacts",
+        }
+    }
+
+    pub fn by_command(command: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|target| target.command() == command)
+    }
+
+    pub fn extensions(self) -> Vec<&'static str> {
+        match self {
+            Self::Auxiliary => AUXILIARY_EXTENSIONS.to_vec(),
+            Self::Artifacts => [AUXILIARY_EXTENSIONS, ARTIFACT_EXTENSIONS].concat(),
+        }
+    }
+}
+
+pub async fn clean(
+    snapshot: &Snapshot,
+    tex_uri: &Uri,
+    target: CleanTarget,
+    options: &Options,
+    current_dir: &Path,
+) -> Vec<Uri> {
+    let root_uri = snapshot
+        .parent(tex_uri)
+        .map(|doc| doc.uri.clone())
+        .unwrap_or_else(|| tex_uri.clone());
+
+    let mut targets = Vec::new();
+    for extension in target.extensions() {
+        targets.extend(
+            snapshot
+                .resolve_aux_targets(&root_uri, options, current_dir, extension)
+                .unwrap_or_default(),
+        );
+    }
+    targets.extend(included_aux_files(
+        snapshot,
+        &root_uri,
+        options,
+        current_dir,
+    ));
+
+    let mut deleted_files = Vec::new();
+    for uri in targets.into_iter().filter(|uri| uri.scheme() == "file") {
+        let path = match uri.to_file_path() {
+            Ok(path) => path,
+            Err(()) => continue,
+        };
+
+        if !path.is_file() {
+            continue;
+        }
+
+        match fs::remove_file(&path).await {
+            Ok(()) => deleted_files.push(uri),
+            Err(why) => warn!("Unable to delete {}: {}", path.to_string_lossy(), why),
+        }
+    }
+    deleted_files
+}
+
+/// Finds the auxiliary files of the documents that are included with `\include`.
+/// LaTeX writes them relative to the build directory of the root document.
+fn included_aux_files(
+    snapshot: &Snapshot,
+    root_uri: &Uri,
+    options: &Options,
+    current_dir: &Path,
+) -> Vec<Uri> {
+    let locations = match root_uri
+        .to_file_path()
+        .ok()
+        .and_then(|path| OutputLocations::resolve(&path, options, current_dir))
+    {
+        Some(locations) => locations,
+        None => return Vec::new(),
+    };
+
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut stack = vec![root_uri.clone()];
+    while let Some(uri) = stack.pop() {
+        if !visited.insert(uri.clone()) {
+            continue;
+        }
+
+        let doc = match snapshot.find(&uri) {
+            Some(doc) => doc,
+            None => continue,
+        };
+
+        let table = match doc.content.as_latex() {
+            Some(table) => table,
+            None => continue,
+        };
+
+        for child in snapshot.graph().children(&uri) {
+            stack.push(child.clone());
+            let is_included = table
+                .includes
+                .iter()
+                .filter(|include| {
+                    table
+                        .tree
+                        .as_command(include.parent)
+                        .map_or(false, |cmd| cmd.name.text() == "\\include")
+                })
+                .flat_map(|include| include.all_targets.iter().flatten())
+                .any(|target| target == child);
+
+            let relative_path = match child.to_file_path() {
+                Ok(path) if is_included => match path.strip_prefix(&locations.build_dir) {
+                    Ok(relative_path) => relative_path.with_extension("aux"),
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+
+            for dir in locations.directories("aux") {
+                files.extend(Uri::from_file_path(dir.join(&relative_path)).ok());
+            }
+        }
+    }
+    files
+}
@@ -0,0 +1,258 @@
+use crate::{
+    feature::{FeatureProvider, FeatureRequest},
+    protocol::{
+        code_action_kind, CodeAction, CodeActionOrCommand, CodeActionParams, Position, Range,
+        RangeExt, TextEdit, WorkspaceEdit,
+    },
+    syntax::SyntaxNode,
+    workspace::DocumentContent,
+};
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LatexUnicodeCodeActionProvider;
+
+#[async_trait]
+impl FeatureProvider for LatexUnicodeCodeActionProvider {
+    type Params = CodeActionParams;
+    type Output = Vec<CodeActionOrCommand>;
+
+    async fn execute<'a>(&'a self, req: &'a FeatureRequest<Self::Params>) -> Self::Output {
+        let mut actions = Vec::new();
+        let table = match &req.current().content {
+            DocumentContent::Latex(table) => table,
+            DocumentContent::Bibtex(_) => return actions,
+        };
+
+        if !req.is_unicode_input_allowed() {
+            return actions;
+        }
+
+        let mut glyphs_by_name = HashMap::new();
+        let mut names_by_glyph = HashMap::new();
+        for comp in req.view.components() {
+            for cmd in &comp.commands {
+                if let Some(glyph) = cmd.glyph.as_deref().filter(|glyph| !glyph.is_ascii()) {
+                    glyphs_by_name.entry(cmd.name.as_str()).or_insert(glyph);
+                    names_by_glyph
+                        .entry(glyph)
+                        .or_insert_with(|| cmd.name.as_str());
+                }
+            }
+        }
+
+        let range = req.params.range;
+        let lines: Vec<_> = req.current().text.lines().collect();
+        let glyph_edits: Vec<_> = table
+            .commands
+            .iter()
+            .filter_map(|node| table.as_command(*node))
+            .filter_map(|cmd| {
+                let start = to_utf16(&lines, cmd.name.start());
+                let end = to_utf16(&lines, cmd.name.end());
+                if !range.contains(start) || !range.contains(end) {
+                    return None;
+                }
+
+                let glyph = glyphs_by_name.get(&cmd.name.text()[1..])?;
+                Some(TextEdit::new(Range::new(start, end), (*glyph).into()))
+            })
+            .collect();
+
+        let mut command_edits = Vec::new();
+        for (line, text) in lines.iter().enumerate().skip(range.start.line as usize) {
+            if line as u64 > range.end.line {
+                break;
+            }
+
+            let mut character = 0;
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                let start = Position::new(line as u64, character);
+                character += c.len_utf16() as u64;
+                let end = Position::new(line as u64, character);
+                if !range.contains(start) || !range.contains(end) {
+                    continue;
+                }
+
+                let mut buf = [0; 4];
+                if let Some(name) = names_by_glyph.get(&*c.encode_utf8(&mut buf)) {
+                    let mut new_text = format!("\\{}", name);
+                    if chars
+                        .peek()
+                        .map_or(false, |next| next.is_ascii_alphabetic())
+                    {
+                        new_text.push(' ');
+                    }
+                    command_edits.push(TextEdit::new(Range::new(start, end), new_text));
+                }
+            }
+        }
+
+        let uri = &req.current().uri;
+        let mut push = |title: &str, edits: Vec<TextEdit>| {
+            if edits.is_empty() {
+                return;
+            }
+
+            let mut changes = HashMap::new();
+            changes.insert(uri.clone().into(), edits);
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.into(),
+                kind: Some(code_action_kind::REFACTOR_REWRITE.into()),
+                diagnostics: None,
+                edit: Some(WorkspaceEdit::new(changes)),
+                command: None,
+                is_preferred: None,
+            }));
+        };
+
+        push("Convert commands to Unicode characters", glyph_edits);
+        push("Convert Unicode characters to commands", command_edits);
+        actions
+    }
+}
+
+fn to_utf16(lines: &[&str], position: Position) -> Position {
+    let character = lines
+        .get(position.line as usize)
+        .map_or(position.character, |line| {
+            line.chars()
+                .take(position.character as usize)
+                .map(|c| c.len_utf16() as u64)
+                .sum()
+        });
+    Position::new(position.line, character)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feature::FeatureTester;
+    use indoc::indoc;
+
+    fn edits(action: &CodeActionOrCommand) -> Vec<TextEdit> {
+        match action {
+            CodeActionOrCommand::CodeAction(action) => action
+                .edit
+                .as_ref()
+                .and_then(|edit| edit.changes.as_ref())
+                .and_then(|changes| changes.values().next().cloned())
+                .unwrap(),
+            CodeActionOrCommand::Command(_) => unreachable!(),
+        }
+    }
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let actual_actions = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .test_code_action(LatexUnicodeCodeActionProvider)
+            .await;
+
+        assert!(actual_actions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let actual_actions = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .test_code_action(LatexUnicodeCodeActionProvider)
+            .await;
+
+        assert!(actual_actions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unicode_input_not_allowed() {
+        let actual_actions = FeatureTester::new()
+            .file("main.tex", r#"$\alpha$"#)
+            .main("main.tex")
+            .range(0, 0, 0, 8)
+            .test_code_action(LatexUnicodeCodeActionProvider)
+            .await;
+
+        assert!(actual_actions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn commands_to_glyphs() {
+        let actual_actions = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \usepackage{unicode-math}
+                        $\alpha + \beta$ $\gamma$
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .range(1, 0, 1, 16)
+            .test_code_action(LatexUnicodeCodeActionProvider)
+            .await;
+
+        assert_eq!(actual_actions.len(), 1);
+        assert_eq!(
+            edits(&actual_actions[0]),
+            vec![
+                TextEdit::new(Range::new_simple(1, 1, 1, 7), "α".into()),
+                TextEdit::new(Range::new_simple(1, 10, 1, 15), "β".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn glyphs_to_commands() {
+        let actual_actions = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        % !TEX program = lualatex
+                        $αx + β$
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .range(1, 0, 1, 8)
+            .test_code_action(LatexUnicodeCodeActionProvider)
+            .await;
+
+        assert_eq!(actual_actions.len(), 1);
+        assert_eq!(
+            edits(&actual_actions[0]),
+            vec![
+                TextEdit::new(Range::new_simple(1, 1, 1, 2), "\\alpha ".into()),
+                TextEdit::new(Range::new_simple(1, 6, 1, 7), "\\beta".into()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn glyphs_to_commands_astral_plane() {
+        let actual_actions = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        % !TEX program = lualatex
+                        $𝐀α$
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .range(1, 0, 1, 5)
+            .test_code_action(LatexUnicodeCodeActionProvider)
+            .await;
+
+        assert_eq!(actual_actions.len(), 1);
+        assert!(edits(&actual_actions[0]).contains(&TextEdit::new(
+            Range::new_simple(1, 3, 1, 4),
+            "\\alpha".into()
+        )));
+    }
+}
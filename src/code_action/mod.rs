@@ -0,0 +1,36 @@
+mod latex_unicode;
+
+use self::latex_unicode::LatexUnicodeCodeActionProvider;
+use crate::{
+    feature::{ConcatProvider, FeatureProvider, FeatureRequest},
+    protocol::{CodeActionOrCommand, CodeActionParams},
+};
+use async_trait::async_trait;
+
+pub struct CodeActionProvider {
+    provider: ConcatProvider<CodeActionParams, CodeActionOrCommand>,
+}
+
+impl CodeActionProvider {
+    pub fn new() -> Self {
+        Self {
+            provider: ConcatProvider::new(vec![Box::new(LatexUnicodeCodeActionProvider)]),
+        }
+    }
+}
+
+impl Default for CodeActionProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl FeatureProvider for CodeActionProvider {
+    type Params = CodeActionParams;
+    type Output = Vec<CodeActionOrCommand>;
+
+    async fn execute<'a>(&'a self, req: &'a FeatureRequest<Self::Params>) -> Self::Output {
+        self.provider.execute(req).await
+    }
+}
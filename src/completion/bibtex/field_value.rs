@@ -0,0 +1,313 @@
+use crate::{
+    completion::types::{Item, ItemData},
+    feature::FeatureRequest,
+    protocol::{CompletionParams, Range},
+    syntax::{bibtex, SyntaxNode, LANGUAGE_DATA},
+    workspace::DocumentContent,
+};
+use petgraph::graph::NodeIndex;
+use std::borrow::Cow;
+
+const MONTHS: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+pub async fn complete_bibtex_field_values<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    items: &mut Vec<Item<'a>>,
+) {
+    let tree = match &req.current().content {
+        DocumentContent::Bibtex(tree) => tree.as_ref(),
+        DocumentContent::Latex(_) => return,
+    };
+
+    let pos = req.params.text_document_position.position;
+    let path = tree.find(pos);
+    let field_index = match path.iter().rposition(|node| tree.as_field(*node).is_some()) {
+        Some(index) => index,
+        None => return,
+    };
+
+    let field_node = path[field_index];
+    let field = tree.as_field(field_node).unwrap();
+    match &field.assign {
+        Some(assign) if assign.end() <= pos => (),
+        _ => return,
+    };
+
+    let mut is_macro = true;
+    let mut range = Range::new(pos, pos);
+    for node in &path[field_index + 1..] {
+        let content = match &tree.graph[*node] {
+            bibtex::Node::Word(word) if is_macro => {
+                range = word.range();
+                continue;
+            }
+            bibtex::Node::Word(_) | bibtex::Node::Concat(_) => continue,
+            bibtex::Node::QuotedContent(content) => (&content.left, content.right.as_ref()),
+            bibtex::Node::BracedContent(content) => (&content.left, content.right.as_ref()),
+            _ => return,
+        };
+
+        if is_macro {
+            let (left, right) = content;
+            let end = right.map_or(pos, |right| right.start());
+            if pos < left.end() || pos > end {
+                return;
+            }
+            is_macro = false;
+            range = Range::new(left.end(), end);
+        }
+    }
+
+    let name = field.name.text().to_lowercase();
+    let mut push = |name: Cow<'a, str>, kind: &'static str, is_text: bool| {
+        // Text inserted over a bare word would be read as a macro otherwise.
+        let braced = is_macro && is_text;
+        items.push(Item::new(
+            range,
+            ItemData::FieldValue { name, kind, braced },
+        ));
+    };
+
+    for value in LANGUAGE_DATA.field_values(&name) {
+        push(value.into(), "value", true);
+    }
+
+    let bib_trees = req
+        .related()
+        .iter()
+        .filter_map(|doc| doc.content.as_bibtex());
+
+    match name.as_str() {
+        "crossref" | "xref" | "related" => {
+            let entry_node = path[field_index - 1];
+            for other in bib_trees.clone() {
+                for node in other.children(other.root) {
+                    if std::ptr::eq(other, tree) && node == entry_node {
+                        continue;
+                    }
+
+                    if let Some(key) = other
+                        .as_entry(node)
+                        .filter(|entry| !entry.is_comment())
+                        .and_then(|entry| entry.key.as_ref())
+                    {
+                        push(key.text().into(), "entry", true);
+                    }
+                }
+            }
+        }
+        "journal" | "journaltitle" => {
+            let mut journals = Vec::new();
+            for other in bib_trees.clone() {
+                for entry_node in other.children(other.root) {
+                    for field in journal_fields(other, entry_node) {
+                        if !std::ptr::eq(other, tree) || field != field_node {
+                            journals.extend(other.field_text(field));
+                        }
+                    }
+                }
+            }
+            journals.sort();
+            journals.dedup();
+            for journal in journals {
+                push(journal.into(), "journal", true);
+            }
+        }
+        _ => (),
+    }
+
+    if is_macro {
+        for other in bib_trees {
+            for string in other
+                .children(other.root)
+                .filter_map(|node| other.as_string(node))
+            {
+                if let Some(name) = &string.name {
+                    push(name.text().into(), "string", false);
+                }
+            }
+        }
+
+        if name == "month" {
+            for month in MONTHS {
+                push((*month).into(), "month", false);
+            }
+        }
+    }
+}
+
+fn journal_fields<'a>(
+    tree: &'a bibtex::Tree,
+    entry_node: NodeIndex,
+) -> impl Iterator<Item = NodeIndex> + 'a {
+    tree.children(entry_node).filter(move |node| {
+        tree.as_field(*node)
+            .map(|field| field.name.text().to_lowercase())
+            .filter(|name| name == "journal" || name == "journaltitle")
+            .is_some()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        feature::FeatureTester,
+        protocol::{Range, RangeExt},
+    };
+    use indoc::indoc;
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let req = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let req = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn macros() {
+        let req = FeatureTester::new()
+            .file(
+                "main.bib",
+                indoc!(
+                    r#"
+                        @string{acm = "ACM"}
+                        @article{foo, month = ja}
+                    "#
+                ),
+            )
+            .main("main.bib")
+            .position(1, 24)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        let labels: Vec<_> = actual_items.iter().map(|item| item.data.label()).collect();
+        assert!(labels.contains(&"acm"));
+        assert!(labels.contains(&"jan"));
+        assert_eq!(actual_items[0].range, Range::new_simple(1, 22, 1, 24));
+    }
+
+    #[tokio::test]
+    async fn crossref_keys() {
+        let req = FeatureTester::new()
+            .file(
+                "main.bib",
+                indoc!(
+                    r#"
+                        @book{bar,}
+                        @inproceedings{foo, crossref = {}}
+                    "#
+                ),
+            )
+            .main("main.bib")
+            .position(1, 32)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        let labels: Vec<_> = actual_items.iter().map(|item| item.data.label()).collect();
+        assert_eq!(labels, vec!["bar"]);
+        assert_eq!(actual_items[0].range, Range::new_simple(1, 32, 1, 32));
+    }
+
+    #[tokio::test]
+    async fn journals() {
+        let req = FeatureTester::new()
+            .file(
+                "main.bib",
+                indoc!(
+                    r#"
+                        @article{bar, journal = {Journal of Graph Theory}}
+                        @article{foo, journal = {Jou}}
+                    "#
+                ),
+            )
+            .main("main.bib")
+            .position(1, 28)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        let labels: Vec<_> = actual_items.iter().map(|item| item.data.label()).collect();
+        assert_eq!(labels, vec!["Journal of Graph Theory"]);
+        assert_eq!(actual_items[0].range, Range::new_simple(1, 25, 1, 28));
+    }
+
+    #[tokio::test]
+    async fn journals_bare_word() {
+        let req = FeatureTester::new()
+            .file(
+                "main.bib",
+                indoc!(
+                    r#"
+                        @article{bar, journal = {Nature Physics}}
+                        @article{foo, journal = Nat}
+                    "#
+                ),
+            )
+            .main("main.bib")
+            .position(1, 27)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items.len(), 1);
+        match &actual_items[0].data {
+            ItemData::FieldValue { name, braced, .. } => {
+                assert_eq!(name, "Nature Physics");
+                assert!(braced);
+            }
+            _ => panic!("Expected a field value"),
+        }
+        assert_eq!(actual_items[0].range, Range::new_simple(1, 24, 1, 27));
+    }
+
+    #[tokio::test]
+    async fn field_name() {
+        let req = FeatureTester::new()
+            .file("main.bib", "@article{foo, langid = {}}")
+            .main("main.bib")
+            .position(0, 16)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_bibtex_field_values(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+}
@@ -2,13 +2,12 @@ use super::combinators::{self, ArgumentContext, Parameter};
 use crate::{
     completion::types::{Item, ItemData},
     feature::FeatureRequest,
-    protocol::{BibtexFormattingOptions, CompletionParams},
-    syntax::{bibtex, BibtexEntryTypeCategory, Structure, LANGUAGE_DATA},
+    protocol::{CompletionParams, Range, RangeExt},
+    syntax::{bibtex, latex, BibtexEntryTypeCategory, Structure, SyntaxNode, LANGUAGE_DATA},
     workspace::{Document, DocumentContent},
 };
-use once_cell::sync::Lazy;
+use itertools::Itertools;
 use petgraph::graph::NodeIndex;
-use regex::Regex;
 
 pub async fn complete_latex_citations<'a>(
     req: &'a FeatureRequest<CompletionParams>,
@@ -20,10 +19,21 @@ pub async fn complete_latex_citations<'a>(
     });
 
     combinators::argument(req, parameters, |ctx| async move {
-        for doc in req.related() {
+        let query = find_query(req, ctx);
+        let range = query.as_ref().map_or(ctx.range, |query| query.range);
+        let mut docs: Vec<_> = req
+            .related()
+            .iter()
+            .filter(|doc| doc.content.as_bibtex().is_some())
+            .collect();
+        docs.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+
+        for (group, doc) in docs.into_iter().enumerate() {
             if let DocumentContent::Bibtex(tree) = &doc.content {
                 for entry_node in tree.children(tree.root) {
-                    if let Some(item) = make_item(ctx, doc, tree, entry_node) {
+                    if let Some(item) =
+                        make_item(range, query.as_ref(), group, doc, tree, entry_node)
+                    {
                         items.push(item);
                     }
                 }
@@ -33,8 +43,184 @@ pub async fn complete_latex_citations<'a>(
     .await;
 }
 
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Query {
+    range: Range,
+    text: String,
+    terms: Vec<QueryTerm>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct QueryTerm {
+    field: Option<String>,
+    value: String,
+}
+
+impl Query {
+    fn parse(words: &[&latex::Token]) -> Option<Self> {
+        let first = words.first()?;
+        let last = words.last()?;
+        if !first.text().starts_with('@') {
+            return None;
+        }
+
+        let terms = words
+            .iter()
+            .enumerate()
+            .map(|(i, word)| {
+                if i == 0 {
+                    &word.text()[1..]
+                } else {
+                    word.text()
+                }
+            })
+            .filter(|term| !term.is_empty())
+            .map(|term| match term.find(':') {
+                Some(index) => QueryTerm {
+                    field: Some(term[..index].to_lowercase()),
+                    value: term[index + 1..].to_lowercase(),
+                },
+                None => QueryTerm {
+                    field: None,
+                    value: term.to_lowercase(),
+                },
+            })
+            .collect();
+
+        Some(Self {
+            range: Range::new(first.start(), last.end()),
+            text: words.iter().map(|word| word.text()).join(" "),
+            terms,
+        })
+    }
+
+    fn matches(&self, info: &EntryInfo) -> bool {
+        self.terms.iter().all(|term| {
+            let fields = match term.field.as_deref() {
+                None => vec![
+                    Some(info.key),
+                    info.author.as_deref(),
+                    info.title.as_deref(),
+                    info.year.as_deref(),
+                    info.journal.as_deref(),
+                ],
+                Some("key") => vec![Some(info.key)],
+                Some("author") | Some("editor") => vec![info.author.as_deref()],
+                Some("title") => vec![info.title.as_deref()],
+                Some("year") | Some("date") => vec![info.year.as_deref()],
+                Some("journal") | Some("journaltitle") | Some("booktitle") => {
+                    vec![info.journal.as_deref()]
+                }
+                Some(_) => return false,
+            };
+
+            fields
+                .into_iter()
+                .flatten()
+                .any(|text| text.to_lowercase().contains(&term.value))
+        })
+    }
+}
+
+fn find_query(req: &FeatureRequest<CompletionParams>, ctx: ArgumentContext) -> Option<Query> {
+    let table = req.current().content.as_latex()?;
+    let pos = req.params.text_document_position.position;
+    let group = table.extract_group(ctx.node, latex::GroupKind::Group, ctx.parameter.index)?;
+    let text = table
+        .children(group)
+        .filter_map(|child| table.as_text(child))
+        .find(|text| text.range().contains(pos))?;
+    let words: Vec<_> = text.words.iter().collect();
+    Query::parse(&words)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct EntryInfo<'a> {
+    key: &'a str,
+    author: Option<String>,
+    title: Option<String>,
+    year: Option<String>,
+    journal: Option<String>,
+}
+
+impl<'a> EntryInfo<'a> {
+    fn new(tree: &'a bibtex::Tree, entry_node: NodeIndex) -> Option<Self> {
+        let entry = tree.as_entry(entry_node)?;
+        let year = field_text(tree, entry_node, &["year"]).or_else(|| {
+            field_text(tree, entry_node, &["date"]).map(|date| date.chars().take(4).collect())
+        });
+
+        Some(Self {
+            key: entry.key.as_ref()?.text(),
+            author: field_text(tree, entry_node, &["author", "editor"]),
+            title: field_text(tree, entry_node, &["title"]),
+            year,
+            journal: field_text(tree, entry_node, &["journal", "journaltitle", "booktitle"]),
+        })
+    }
+
+    fn text(&self) -> String {
+        let fields = [&self.author, &self.title, &self.year, &self.journal];
+        let mut text = self.key.to_owned();
+        for field in fields.iter().filter_map(|field| field.as_ref()) {
+            text.push(' ');
+            text.push_str(field);
+        }
+        text
+    }
+
+    fn detail(&self, file_name: &str) -> String {
+        let mut detail = self.author.as_deref().map(short_author).unwrap_or_default();
+        if let Some(year) = &self.year {
+            if !detail.is_empty() {
+                detail.push(' ');
+            }
+            detail.push_str(&format!("({})", year));
+        }
+
+        if let Some(title) = &self.title {
+            if !detail.is_empty() {
+                detail.push_str(" \u{2013} ");
+            }
+            detail.push_str(title);
+        }
+
+        if !detail.is_empty() {
+            detail.push(' ');
+        }
+        detail.push_str(&format!("[{}]", file_name));
+        detail
+    }
+}
+
+fn field_text(tree: &bibtex::Tree, entry_node: NodeIndex, names: &[&str]) -> Option<String> {
+    names
+        .iter()
+        .find_map(|name| tree.field_by_name(entry_node, name))
+        .and_then(|field| tree.field_text(field))
+}
+
+fn short_author(authors: &str) -> String {
+    let last_names: Vec<_> = authors
+        .split(" and ")
+        .map(|name| match name.find(',') {
+            Some(index) => name[..index].trim(),
+            None => name.split_whitespace().last().unwrap_or_default(),
+        })
+        .collect();
+
+    match last_names.as_slice() {
+        [first] => (*first).to_owned(),
+        [first, second] => format!("{} and {}", first, second),
+        [first, ..] => format!("{} et al.", first),
+        [] => String::new(),
+    }
+}
+
 fn make_item<'a>(
-    ctx: ArgumentContext,
+    range: Range,
+    query: Option<&Query>,
+    group: usize,
     doc: &'a Document,
     tree: &'a bibtex::Tree,
     entry_node: NodeIndex,
@@ -44,48 +230,34 @@ fn make_item<'a>(
         return None;
     }
 
-    let key = entry.key.as_ref()?.text();
-    let options = BibtexFormattingOptions::default();
-    let params = bibtex::FormattingParams {
-        insert_spaces: true,
-        tab_size: 4,
-        options: &options,
-    };
-    let entry_code = bibtex::format(tree, entry_node, params);
-    let text = format!(
-        "{} {}",
-        &key,
-        WHITESPACE_REGEX
-            .replace_all(
-                &entry_code
-                    .replace('{', "")
-                    .replace('}', "")
-                    .replace(',', " ")
-                    .replace('=', " "),
-                " ",
-            )
-            .trim()
-    );
+    let info = EntryInfo::new(tree, entry_node)?;
+    if let Some(query) = query {
+        if !query.matches(&info) {
+            return None;
+        }
+    }
 
     let ty = LANGUAGE_DATA
         .find_entry_type(&entry.ty.text()[1..])
         .map(|ty| Structure::Entry(ty.category))
         .unwrap_or_else(|| Structure::Entry(BibtexEntryTypeCategory::Misc));
 
+    let file_name = doc.uri.path().rsplit('/').next().unwrap_or_default();
     let item = Item::new(
-        ctx.range,
+        range,
         ItemData::Citation {
             uri: &doc.uri,
-            key,
-            text,
+            key: info.key,
+            text: query.map_or_else(|| info.text(), |query| query.text.clone()),
+            detail: info.detail(file_name),
             ty,
+            group,
+            is_query: query.is_some(),
         },
     );
     Some(item)
 }
 
-static WHITESPACE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\\s+").unwrap());
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -258,4 +430,119 @@ mod tests {
 
         assert!(actual_items.is_empty());
     }
+
+    #[tokio::test]
+    async fn entry_detail() {
+        let req = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \addbibresource{bar.bib}
+                        \cite{}
+                    "#
+                ),
+            )
+            .file(
+                "bar.bib",
+                indoc!(
+                    r#"
+                        @article{foo,
+                            author = {Smith, John and Doe, Jane},
+                            title = {Graphs},
+                            year = {2019},
+                        }
+                    "#
+                ),
+            )
+            .main("foo.tex")
+            .position(1, 6)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_citations(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items.len(), 1);
+        match &actual_items[0].data {
+            ItemData::Citation { detail, text, .. } => {
+                assert_eq!(detail, "Smith and Doe (2019) \u{2013} Graphs [bar.bib]");
+                assert_eq!(text, "foo Smith, John and Doe, Jane Graphs 2019");
+            }
+            _ => panic!("Expected a citation"),
+        }
+    }
+
+    #[tokio::test]
+    async fn field_query() {
+        let req = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \addbibresource{bar.bib}
+                        \cite{@author:smith year:2019}
+                    "#
+                ),
+            )
+            .file(
+                "bar.bib",
+                indoc!(
+                    r#"
+                        @article{foo, author = {John Smith}, year = {2019}}
+                        @article{bar, author = {John Smith}, year = {2020}}
+                        @article{baz, author = {Jane Doe}, year = {2019}}
+                    "#
+                ),
+            )
+            .main("foo.tex")
+            .position(1, 20)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_citations(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items.len(), 1);
+        assert_eq!(actual_items[0].data.label(), "foo");
+        assert_eq!(actual_items[0].range, Range::new_simple(1, 6, 1, 29));
+    }
+
+    #[tokio::test]
+    async fn group_by_file() {
+        let req = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \addbibresource{baz.bib}
+                        \addbibresource{bar.bib}
+                        \cite{}
+                    "#
+                ),
+            )
+            .file("bar.bib", "@article{foo,}")
+            .file("baz.bib", "@article{bar,}")
+            .main("foo.tex")
+            .position(2, 6)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_citations(&req, &mut actual_items).await;
+
+        let groups: Vec<_> = actual_items
+            .iter()
+            .map(|item| match &item.data {
+                ItemData::Citation {
+                    key, detail, group, ..
+                } => (*key, detail.as_str(), *group),
+                _ => panic!("Expected a citation"),
+            })
+            .collect();
+        assert_eq!(
+            groups,
+            vec![("foo", "[bar.bib]", 0), ("bar", "[baz.bib]", 1)]
+        );
+    }
 }
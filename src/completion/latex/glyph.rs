@@ -0,0 +1,123 @@
+use super::combinators;
+use crate::{
+    completion::types::{Item, ItemData},
+    feature::FeatureRequest,
+    protocol::CompletionParams,
+    syntax::SyntaxNode,
+};
+
+pub async fn complete_latex_glyphs<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    items: &mut Vec<Item<'a>>,
+) {
+    if !req.is_unicode_input_allowed() {
+        return;
+    }
+
+    combinators::command(req, |cmd_node| async move {
+        let table = req.current().content.as_latex().unwrap();
+        let range = table.as_command(cmd_node).unwrap().name.range();
+
+        for comp in req.view.components() {
+            for cmd in &comp.commands {
+                if let Some(glyph) = cmd.glyph.as_deref().filter(|glyph| !glyph.is_ascii()) {
+                    items.push(Item::new(
+                        range,
+                        ItemData::Glyph {
+                            name: &cmd.name,
+                            glyph,
+                        },
+                    ));
+                }
+            }
+        }
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        feature::FeatureTester,
+        protocol::{Range, RangeExt},
+    };
+    use indoc::indoc;
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let req = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_glyphs(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unicode_input_not_allowed() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\alp"#)
+            .main("main.tex")
+            .position(0, 4)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_glyphs(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn magic_comment() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        % !TEX program = xelatex
+                        \alp
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 4)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_glyphs(&req, &mut actual_items).await;
+
+        assert!(actual_items.iter().any(|item| item.data.label() == "α"));
+        assert_eq!(actual_items[0].range, Range::new_simple(1, 0, 1, 4));
+    }
+
+    #[tokio::test]
+    async fn unicode_math_package() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \usepackage{unicode-math}
+                        \alp
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 4)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_glyphs(&req, &mut actual_items).await;
+
+        assert!(actual_items.iter().any(|item| item.data.label() == "α"));
+    }
+}
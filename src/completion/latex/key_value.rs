@@ -0,0 +1,287 @@
+use crate::{
+    completion::types::{Item, ItemData},
+    components::{KeyValueTarget, KeyValueTargetKind, KeyValueType, COMPONENT_DATABASE},
+    feature::FeatureRequest,
+    protocol::{CompletionParams, Position, Range, RangeExt},
+    syntax::{latex, AstNodeIndex, SyntaxNode, LANGUAGE_DATA},
+    workspace::DocumentContent,
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct KeyValueContext<'a> {
+    pub target: &'static KeyValueTarget,
+    pub group: AstNodeIndex,
+    pub word: Option<&'a latex::Token>,
+}
+
+impl<'a> KeyValueContext<'a> {
+    pub fn find(table: &'a latex::SymbolTable, pos: Position) -> Option<Self> {
+        let path = table.find(pos);
+        let index = path
+            .iter()
+            .rposition(|node| table.as_group(*node).is_some())?;
+        if path[index + 1..]
+            .iter()
+            .any(|node| !matches!(table[*node], latex::Node::Text(_) | latex::Node::Comma(_)))
+        {
+            return None;
+        }
+
+        let group_node = path[index];
+        let group = table.as_group(group_node)?;
+        if pos < group.left.end()
+            || (group.right.is_some() && !group.range().contains_exclusive(pos))
+        {
+            return None;
+        }
+
+        let parent = *path.get(index.checked_sub(1)?)?;
+        let cmd = table.as_command(parent)?;
+        let braced = group.kind == latex::GroupKind::Group;
+        let find_target = |kind| {
+            table
+                .extract_comma_separated_words(parent, latex::GroupKind::Group, 0)?
+                .into_iter()
+                .find_map(|word| COMPONENT_DATABASE.key_value_target(kind, word.text(), braced))
+        };
+
+        let target = match (&cmd.name.text()[1..], braced) {
+            ("usepackage", false) | ("RequirePackage", false) => {
+                find_target(KeyValueTargetKind::Package)?
+            }
+            ("documentclass", false) => find_target(KeyValueTargetKind::Class)?,
+            ("begin", false) => find_target(KeyValueTargetKind::Environment)?,
+            (name, _) => {
+                COMPONENT_DATABASE.key_value_target(KeyValueTargetKind::Command, name, braced)?
+            }
+        };
+
+        let word = table
+            .children(group_node)
+            .filter_map(|child| table.as_text(child))
+            .flat_map(|text| text.words.iter())
+            .find(|word| word.range().contains(pos));
+
+        Some(Self {
+            target,
+            group: group_node,
+            word,
+        })
+    }
+}
+
+pub async fn complete_latex_key_values<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    items: &mut Vec<Item<'a>>,
+) {
+    let table = match &req.current().content {
+        DocumentContent::Latex(table) => table,
+        DocumentContent::Bibtex(_) => return,
+    };
+
+    let pos = req.params.text_document_position.position;
+    let ctx = match KeyValueContext::find(table, pos) {
+        Some(ctx) => ctx,
+        None => return,
+    };
+
+    if ctx.target.concatenated {
+        let text = ctx.word.map(|word| word.text()).unwrap_or_default();
+        for key in ctx
+            .target
+            .keys
+            .iter()
+            .filter(|key| !text.contains(key.name.as_str()))
+        {
+            let data = ItemData::KeyValueKey {
+                key,
+                query: "",
+                has_value: false,
+                concatenated: true,
+            };
+            items.push(Item::new(Range::new(pos, pos), data));
+        }
+        return;
+    }
+
+    let word = match ctx.word {
+        Some(word) => word,
+        None => {
+            for key in &ctx.target.keys {
+                let data = ItemData::KeyValueKey {
+                    key,
+                    query: "",
+                    has_value: false,
+                    concatenated: false,
+                };
+                items.push(Item::new(Range::new(pos, pos), data));
+            }
+            return;
+        }
+    };
+
+    let start = word.start();
+    let offset =
+        |text: &str| Position::new(start.line, start.character + text.chars().count() as u64);
+    let text = word.text();
+    let cursor = text
+        .char_indices()
+        .nth((pos.character - start.character) as usize)
+        .map_or(text.len(), |(i, _)| i);
+
+    match text.find('=') {
+        Some(index) if index < cursor => {
+            if let Some(key) = ctx.target.find(&text[..index]) {
+                let range = Range::new(offset(&text[..=index]), word.end());
+                let colors = LANGUAGE_DATA
+                    .colors
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|_| key.ty == KeyValueType::Color);
+                let query = &text[index + 1..];
+                for name in key.values().into_iter().chain(colors) {
+                    items.push(Item::new(range, ItemData::KeyValueValue { name, query }));
+                }
+            }
+        }
+        Some(index) => {
+            let range = Range::new(start, offset(&text[..index]));
+            for key in &ctx.target.keys {
+                let data = ItemData::KeyValueKey {
+                    key,
+                    query: &text[..index],
+                    has_value: true,
+                    concatenated: false,
+                };
+                items.push(Item::new(range, data));
+            }
+        }
+        None => {
+            for key in &ctx.target.keys {
+                let data = ItemData::KeyValueKey {
+                    key,
+                    query: text,
+                    has_value: false,
+                    concatenated: false,
+                };
+                items.push(Item::new(word.range(), data));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{feature::FeatureTester, protocol::RangeExt};
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let req = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let req = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn package_option_key() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\usepackage[mar]{geometry}"#)
+            .main("main.tex")
+            .position(0, 14)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        assert!(actual_items
+            .iter()
+            .any(|item| item.data.label() == "margin"));
+        assert_eq!(actual_items[0].range, Range::new_simple(0, 12, 0, 15));
+    }
+
+    #[tokio::test]
+    async fn command_option_value() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\includegraphics[keepaspectratio=]{foo}"#)
+            .main("main.tex")
+            .position(0, 33)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        let labels: Vec<_> = actual_items.iter().map(|item| item.data.label()).collect();
+        assert_eq!(labels, vec!["true", "false"]);
+        assert_eq!(actual_items[0].range, Range::new_simple(0, 33, 0, 33));
+    }
+
+    #[tokio::test]
+    async fn command_option_value_query() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\includegraphics[keepaspectratio=fa]{foo}"#)
+            .main("main.tex")
+            .position(0, 35)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        match actual_items[0].data {
+            ItemData::KeyValueValue { query, .. } => assert_eq!(query, "fa"),
+            _ => panic!("expected a value"),
+        }
+        assert_eq!(actual_items[0].range, Range::new_simple(0, 33, 0, 35));
+    }
+
+    #[tokio::test]
+    async fn environment_placement() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\begin{figure}[h]"#)
+            .main("main.tex")
+            .position(0, 16)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        let labels: Vec<_> = actual_items.iter().map(|item| item.data.label()).collect();
+        assert!(!labels.contains(&"h"));
+        assert!(labels.contains(&"t"));
+        assert_eq!(actual_items[0].range, Range::new_simple(0, 16, 0, 16));
+    }
+
+    #[tokio::test]
+    async fn unknown_package() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\usepackage[]{foo}"#)
+            .main("main.tex")
+            .position(0, 12)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+        complete_latex_key_values(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+}
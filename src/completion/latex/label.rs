@@ -3,18 +3,24 @@ use crate::{
     completion::types::{Item, ItemData},
     feature::{DocumentView, FeatureRequest},
     outline::{Outline, OutlineContext, OutlineContextItem},
-    protocol::{CompletionParams, RangeExt},
+    protocol::{CompletionParams, Position, Range, RangeExt, TextEdit},
     syntax::{
         latex, LatexLabelKind, LatexLabelReferenceSource, Structure, SyntaxNode, LANGUAGE_DATA,
     },
     workspace::DocumentContent,
 };
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use std::sync::Arc;
 
+const SAME_SECTION_BONUS: i64 = 20;
+
+const PROXIMITY_BONUS: i64 = 20;
+
 pub async fn complete_latex_labels<'a>(
     req: &'a FeatureRequest<CompletionParams>,
     items: &mut Vec<Item<'a>>,
 ) {
+    let user_parameters = reference_commands(req).map(|(name, _)| Parameter { name, index: 0 });
     let parameters = LANGUAGE_DATA
         .label_commands
         .iter()
@@ -22,22 +28,28 @@ pub async fn complete_latex_labels<'a>(
         .map(|cmd| Parameter {
             name: &cmd.name[1..],
             index: cmd.index,
-        });
+        })
+        .chain(user_parameters);
 
     combinators::argument(req, parameters, |ctx| async move {
-        let source = find_source(ctx);
+        let source = find_source(req, ctx);
+        let pos = req.params.text_document_position.position;
+        let current_outline = Outline::analyze(&req.view);
+        let current_section = current_outline.find(&req.current().uri, pos);
+
+        let matcher = SkimMatcherV2::default();
+        let mut has_match = false;
+        let typed_name = find_typed_name(req, ctx);
         for doc in req.related() {
             let snapshot = Arc::clone(&req.view.snapshot);
-            let view =
-                DocumentView::analyze(snapshot, Arc::clone(&doc), &req.options, &req.current_dir);
-            let outline = Outline::analyze(&view, &req.options, &req.current_dir);
+            let view = DocumentView::analyze(snapshot, Arc::clone(&doc));
+            let outline = Outline::analyze(&view);
 
             if let DocumentContent::Latex(table) = &doc.content {
                 for label in table
                     .labels
                     .iter()
                     .filter(|label| label.kind == LatexLabelKind::Definition)
-                    .filter(|label| is_included(&table, label, source))
                 {
                     let outline_ctx = OutlineContext::parse(&view, &outline, *label);
 
@@ -50,7 +62,32 @@ pub async fn complete_latex_labels<'a>(
                         None => Structure::Label,
                     };
 
+                    if !is_included(&table, label, kind, source) {
+                        continue;
+                    }
+
+                    let label_pos = table[label.parent].start();
+                    let mut bonus = 0;
+                    if doc.uri == req.current().uri {
+                        let distance = if label_pos.line > pos.line {
+                            label_pos.line - pos.line
+                        } else {
+                            pos.line - label_pos.line
+                        };
+                        bonus += PROXIMITY_BONUS * 10 / (10 + distance as i64);
+                    }
+
+                    let label_section = outline.find(&doc.uri, label_pos);
+                    if let (Some(a), Some(b)) = (current_section, label_section) {
+                        if std::ptr::eq(a, b) {
+                            bonus += SAME_SECTION_BONUS;
+                        }
+                    }
+
                     for name in label.names(&table) {
+                        has_match |= typed_name.map_or(false, |typed| {
+                            matcher.fuzzy_match(name.text(), typed).is_some()
+                        });
                         let header = outline_ctx.as_ref().and_then(|ctx| ctx.detail());
                         let footer = outline_ctx.as_ref().and_then(|ctx| match &ctx.item {
                             OutlineContextItem::Caption { text, .. } => Some(text.clone()),
@@ -70,6 +107,7 @@ pub async fn complete_latex_labels<'a>(
                                 header,
                                 footer,
                                 text,
+                                bonus,
                             },
                         );
                         items.push(item);
@@ -77,26 +115,88 @@ pub async fn complete_latex_labels<'a>(
                 }
             }
         }
+
+        if let (Some(name), false) = (typed_name, has_match) {
+            if let Some(edit) = new_label_edit(req, source, current_section, name) {
+                items.push(Item::new(ctx.range, ItemData::NewLabel { name, edit }));
+            }
+        }
     })
     .await;
 }
 
-fn find_source(ctx: ArgumentContext) -> LatexLabelReferenceSource {
-    match LANGUAGE_DATA
+fn reference_commands<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+) -> impl Iterator<Item = (&'a str, LatexLabelReferenceSource)> {
+    req.options
+        .latex
+        .as_ref()
+        .and_then(|opts| opts.reference_commands.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|(name, source)| (name.trim_start_matches('\\'), *source))
+}
+
+fn find_source(
+    req: &FeatureRequest<CompletionParams>,
+    ctx: ArgumentContext,
+) -> LatexLabelReferenceSource {
+    let builtin_source = LANGUAGE_DATA
         .label_commands
         .iter()
         .find(|cmd| &cmd.name[1..] == ctx.parameter.name && cmd.index == ctx.parameter.index)
-        .map(|cmd| cmd.kind)
+        .map(|cmd| match cmd.kind {
+            LatexLabelKind::Definition => unreachable!(),
+            LatexLabelKind::Reference(source) => source,
+        });
+
+    builtin_source
+        .or_else(|| {
+            reference_commands(req)
+                .find(|(name, _)| *name == ctx.parameter.name)
+                .map(|(_, source)| source)
+        })
         .unwrap()
-    {
-        LatexLabelKind::Definition => unreachable!(),
-        LatexLabelKind::Reference(source) => source,
+}
+
+fn find_typed_name<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    ctx: ArgumentContext,
+) -> Option<&'a str> {
+    let table = req.current().content.as_latex()?;
+    let group = table.extract_group(ctx.node, latex::GroupKind::Group, ctx.parameter.index)?;
+    table
+        .children(group)
+        .filter_map(|child| table.as_text(child))
+        .flat_map(|text| text.words.iter())
+        .find(|word| word.range() == ctx.range)
+        .map(|word| word.text())
+}
+
+fn new_label_edit(
+    req: &FeatureRequest<CompletionParams>,
+    source: LatexLabelReferenceSource,
+    current_section: Option<&latex::Section>,
+    name: &str,
+) -> Option<TextEdit> {
+    // The new label is inserted after the section command.
+    match source {
+        LatexLabelReferenceSource::Everything | LatexLabelReferenceSource::Section => (),
+        _ => return None,
     }
+
+    let table = req.current().content.as_latex()?;
+    let end: Position = table[current_section?.parent].end();
+    Some(TextEdit::new(
+        Range::new(end, end),
+        format!("\n\\label{{{}}}", name),
+    ))
 }
 
 fn is_included(
     table: &latex::SymbolTable,
     label: &latex::Label,
+    kind: Structure,
     source: LatexLabelReferenceSource,
 ) -> bool {
     let label_range = table[label.parent].range();
@@ -107,6 +207,9 @@ fn is_included(
             .iter()
             .filter(|env| env.left.is_math(&table))
             .any(|env| env.range(&table).contains_exclusive(label_range.start)),
+        LatexLabelReferenceSource::Section => kind == Structure::Section,
+        LatexLabelReferenceSource::Float => kind == Structure::Float,
+        LatexLabelReferenceSource::Theorem => kind == Structure::Theorem,
     }
 }
 
@@ -227,4 +330,145 @@ mod tests {
 
         assert_eq!(actual_labels, vec!["foo"]);
     }
+
+    #[tokio::test]
+    async fn user_reference_command() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \begin{figure}\caption{Foo}\label{fig:foo}\end{figure}
+                        \section{Bar}\label{sec:bar}
+                        \figref{}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(2, 8)
+            .reference_command("figref", LatexLabelReferenceSource::Float)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_labels(&req, &mut actual_items).await;
+
+        let actual_labels: Vec<_> = actual_items
+            .into_iter()
+            .map(|item| item.data.label().to_owned())
+            .collect();
+
+        assert_eq!(actual_labels, vec!["fig:foo"]);
+    }
+
+    #[tokio::test]
+    async fn proximity() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \section{Foo}\label{foo}
+                        \section{Bar}\label{bar}
+                        \ref{}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(2, 5)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_labels(&req, &mut actual_items).await;
+
+        let bonuses: Vec<_> = actual_items
+            .iter()
+            .map(|item| match item.data {
+                ItemData::Label { bonus, .. } => bonus,
+                _ => panic!("Expected a label"),
+            })
+            .collect();
+
+        assert_eq!(bonuses.len(), 2);
+        assert!(bonuses[1] > bonuses[0]);
+    }
+
+    #[tokio::test]
+    async fn missing_label() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \section{Foo}
+                        \ref{bar}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 8)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_labels(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items.len(), 1);
+        match &actual_items[0].data {
+            ItemData::NewLabel { name, edit } => {
+                assert_eq!(*name, "bar");
+                assert_eq!(edit.range, Range::new_simple(0, 13, 0, 13));
+                assert_eq!(edit.new_text, "\n\\label{bar}");
+            }
+            _ => panic!("Expected a new label"),
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_label_equation() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \section{Foo}
+                        \eqref{bar}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 10)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_labels(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn partial_label() {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \section{Foo}\label{sec:foo}
+                        \ref{sec}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 8)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_labels(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items.len(), 1);
+        assert!(matches!(actual_items[0].data, ItemData::Label { .. }));
+    }
 }
@@ -6,9 +6,12 @@ pub mod color_model;
 mod combinators;
 pub mod component;
 pub mod glossary;
+pub mod glyph;
 pub mod import;
 pub mod include;
+pub mod key_value;
 pub mod label;
+pub mod snippet;
 pub mod theorem;
 pub mod tikz_lib;
 pub mod user;
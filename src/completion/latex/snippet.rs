@@ -0,0 +1,125 @@
+use super::combinators;
+use crate::{
+    completion::types::{Item, ItemData},
+    feature::FeatureRequest,
+    protocol::{CompletionParams, LatexSnippet},
+    syntax::SyntaxNode,
+};
+use once_cell::sync::Lazy;
+
+static BUILTIN_SNIPPETS: Lazy<Vec<LatexSnippet>> = Lazy::new(|| {
+    const JSON: &str = include_str!("../../../data/snippets.json");
+    serde_json::from_str(JSON).expect("Failed to deserialize snippets.json")
+});
+
+pub async fn complete_latex_snippets<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    items: &mut Vec<Item<'a>>,
+) {
+    combinators::command(req, |cmd_node| async move {
+        let table = req.current().content.as_latex().unwrap();
+        let cmd = table.as_command(cmd_node).unwrap();
+
+        let user_snippets = req
+            .options
+            .latex
+            .as_ref()
+            .and_then(|opts| opts.snippets.as_ref())
+            .into_iter()
+            .flatten();
+
+        for snippet in user_snippets.chain(BUILTIN_SNIPPETS.iter()) {
+            // Bodies without a command replace the backslash that triggered the completion.
+            let range = if snippet.body.starts_with('\\') {
+                cmd.short_name_range()
+            } else {
+                cmd.name.range()
+            };
+            items.push(Item::new(range, ItemData::Snippet { snippet }));
+        }
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        feature::FeatureTester,
+        protocol::{Range, RangeExt},
+    };
+
+    #[test]
+    fn builtin_snippets() {
+        assert!(BUILTIN_SNIPPETS
+            .iter()
+            .any(|snippet| snippet.prefix == "figure"));
+    }
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let req = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_snippets(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let req = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .position(0, 0)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_snippets(&req, &mut actual_items).await;
+
+        assert!(actual_items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn user_snippets_first() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\fig"#)
+            .main("main.tex")
+            .position(0, 4)
+            .snippet("fig", r#"\begin{figure}$0\end{figure}"#)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_snippets(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items[0].data.label(), "fig");
+        assert!(actual_items
+            .iter()
+            .any(|item| item.data.label() == "figure"));
+        assert_eq!(actual_items[0].range, Range::new_simple(0, 1, 0, 4));
+    }
+
+    #[tokio::test]
+    async fn user_snippet_without_backslash() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\hel"#)
+            .main("main.tex")
+            .position(0, 4)
+            .snippet(r#"\hello"#, "Hello, ${1:world}!")
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_snippets(&req, &mut actual_items).await;
+
+        assert_eq!(actual_items[0].data.label(), "hello");
+        assert_eq!(actual_items[0].range, Range::new_simple(0, 0, 0, 4));
+    }
+}
@@ -7,6 +7,79 @@ use crate::{
     workspace::DocumentContent,
 };
 
+pub async fn complete_latex_user_command_definitions<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    items: &mut Vec<Item<'a>>,
+) {
+    combinators::command(req, |current_cmd_node| async move {
+        let current_cmd = req
+            .current()
+            .content
+            .as_latex()
+            .unwrap()
+            .as_command(current_cmd_node)
+            .unwrap();
+
+        for doc in req.related() {
+            if let DocumentContent::Latex(table) = &doc.content {
+                let is_current = doc.uri == req.current().uri;
+                for def in &table.command_definitions {
+                    if is_current && def.definition == current_cmd_node {
+                        continue;
+                    }
+
+                    let name = &def.definition_name(&table)[1..];
+                    let data = ItemData::UserCommandDefinition {
+                        name,
+                        snippet: command_snippet(name, &def.parameters(&table)),
+                        definition: table.print(def.parent),
+                        is_math: false,
+                    };
+                    items.push(Item::new(current_cmd.short_name_range(), data));
+                }
+
+                for op in &table.math_operators {
+                    if is_current && op.definition == current_cmd_node {
+                        continue;
+                    }
+
+                    let name = &op.definition_name(&table)[1..];
+                    let data = ItemData::UserCommandDefinition {
+                        name,
+                        snippet: escape_snippet(name),
+                        definition: table.print(op.parent),
+                        is_math: true,
+                    };
+                    items.push(Item::new(current_cmd.short_name_range(), data));
+                }
+            }
+        }
+    })
+    .await;
+}
+
+fn command_snippet(name: &str, params: &[latex::CommandParameter]) -> String {
+    let mut snippet = escape_snippet(name);
+    for (i, param) in params.iter().enumerate() {
+        match param {
+            latex::CommandParameter::Mandatory => {
+                snippet.push_str(&format!("{{${}}}", i + 1));
+            }
+            latex::CommandParameter::Optional { default } => {
+                let default = escape_snippet(default.as_deref().unwrap_or_default());
+                snippet.push_str(&format!("${{{}:[{}]}}", i + 1, default));
+            }
+        }
+    }
+    snippet
+}
+
+fn escape_snippet(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('$', "\\$")
+        .replace('}', "\\}")
+}
+
 pub async fn complete_latex_user_commands<'a>(
     req: &'a FeatureRequest<CompletionParams>,
     items: &mut Vec<Item<'a>>,
@@ -42,6 +115,28 @@ pub async fn complete_latex_user_commands<'a>(
     .await;
 }
 
+pub async fn complete_latex_user_environment_definitions<'a>(
+    req: &'a FeatureRequest<CompletionParams>,
+    items: &mut Vec<Item<'a>>,
+) {
+    combinators::environment(req, |ctx| async move {
+        for table in req
+            .related()
+            .into_iter()
+            .filter_map(|doc| doc.content.as_latex())
+        {
+            for def in &table.environment_definitions {
+                let data = ItemData::UserEnvironmentDefinition {
+                    name: def.name(&table).text(),
+                    definition: table.print(def.parent),
+                };
+                items.push(Item::new(ctx.range, data));
+            }
+        }
+    })
+    .await;
+}
+
 pub async fn complete_latex_user_environments<'a>(
     req: &'a FeatureRequest<CompletionParams>,
     items: &mut Vec<Item<'a>>,
@@ -172,6 +267,55 @@ mod tests {
         assert_eq!(actual_labels, vec!["include", "bar"]);
     }
 
+    #[tokio::test]
+    async fn command_definition() {
+        let req = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \include{bar}
+                        \newcommand{\vect}[1]{\mathbf{#1}}
+                        \ve
+                    "#
+                ),
+            )
+            .file(
+                "bar.tex",
+                indoc!(
+                    r#"
+                        \newcommand{\pair}[2][0]{(#1, #2)}
+                        \NewDocumentCommand{\norm}{s O{2} m}{\|#3\|_{#2}}
+                        \DeclareMathOperator{\rank}{rank}
+                    "#
+                ),
+            )
+            .main("foo.tex")
+            .position(2, 3)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_user_command_definitions(&req, &mut actual_items).await;
+
+        let actual_snippets: Vec<_> = actual_items
+            .into_iter()
+            .filter_map(|item| match item.data {
+                ItemData::UserCommandDefinition { name, snippet, .. } => Some((name, snippet)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            actual_snippets,
+            vec![
+                ("vect", "vect{$1}".to_owned()),
+                ("pair", "pair${1:[0]}{$2}".to_owned()),
+                ("norm", "norm${1:[2]}{$2}".to_owned()),
+                ("rank", "rank".to_owned()),
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn environment() {
         let req = FeatureTester::new()
@@ -201,4 +345,38 @@ mod tests {
             .collect();
         assert_eq!(actual_labels, vec!["bar"]);
     }
+
+    #[tokio::test]
+    async fn environment_definition() {
+        let req = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \include{bar}
+                        \begin{no}
+                    "#
+                ),
+            )
+            .file(
+                "bar.tex",
+                indoc!(
+                    r#"
+                        \newenvironment{proofsketch}{}{}
+                        \newtcolorbox{note}{}
+                    "#
+                ),
+            )
+            .file("baz.tex", r#"\newenvironment{baz}{}{}"#)
+            .main("foo.tex")
+            .position(1, 9)
+            .test_completion_request()
+            .await;
+        let mut actual_items = Vec::new();
+
+        complete_latex_user_environment_definitions(&req, &mut actual_items).await;
+
+        let actual_labels: Vec<_> = actual_items.iter().map(|item| item.data.label()).collect();
+        assert_eq!(actual_labels, vec!["proofsketch", "note"]);
+    }
 }
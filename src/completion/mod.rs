@@ -3,12 +3,15 @@ mod latex;
 mod types;
 mod util;
 
-pub use self::types::{CompletionItemData, Item, ItemData};
+pub use self::{
+    latex::key_value::KeyValueContext,
+    types::{CompletionItemData, Item, ItemData},
+};
 
 use self::{
     bibtex::{
         cmd::complete_bibtex_commands, entry_type::complete_bibtex_entry_types,
-        field_name::complete_bibtex_fields,
+        field_name::complete_bibtex_fields, field_value::complete_bibtex_field_values,
     },
     latex::{
         argument::complete_latex_arguments,
@@ -18,31 +21,42 @@ use self::{
         color_model::complete_latex_color_models,
         component::{complete_latex_component_commands, complete_latex_component_environments},
         glossary::complete_latex_glossary_entries,
+        glyph::complete_latex_glyphs,
         import::{complete_latex_classes, complete_latex_packages},
         include::complete_latex_includes,
+        key_value::complete_latex_key_values,
         label::complete_latex_labels,
+        snippet::complete_latex_snippets,
         theorem::complete_latex_theorem_environments,
         tikz_lib::{complete_latex_pgf_libraries, complete_latex_tikz_libraries},
-        user::{complete_latex_user_commands, complete_latex_user_environments},
+        user::{
+            complete_latex_user_command_definitions, complete_latex_user_commands,
+            complete_latex_user_environment_definitions, complete_latex_user_environments,
+        },
     },
-    util::{adjust_kind, component_detail, current_word, image_documentation},
+    util::{adjust_kind, component_detail, current_word, image_documentation, is_math_context},
 };
 use crate::{
+    components::KeyValueType,
     feature::{FeatureProvider, FeatureRequest},
     protocol::{
         CompletionItem, CompletionParams, CompletionTextEdit, Documentation, InsertTextFormat,
         MarkupContent, MarkupKind, RangeExt, TextEdit,
     },
-    syntax::{self, Structure, SyntaxNode},
+    syntax::{self, Structure, SyntaxNode, LANGUAGE_DATA},
     workspace::DocumentContent,
 };
 use async_trait::async_trait;
-use fuzzy_matcher::FuzzyMatcher;
 use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use std::collections::HashSet;
 
 pub const COMPLETION_LIMIT: usize = 50;
 
+const USER_DEFINITION_BONUS: i64 = 10;
+
+const MATH_CONTEXT_BONUS: i64 = 20;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub struct CompletionProvider;
 
@@ -62,12 +76,13 @@ impl FeatureProvider for CompletionProvider {
         let mut items = dedup(items);
         preselect(req, &mut items);
         score(req, &mut items);
+        adjust_math_context(req, &mut items);
 
         items.sort_by_key(|item| (!item.preselect, -item.score.unwrap_or(std::i64::MIN + 1)));
         items
             .into_iter()
-            .take(COMPLETION_LIMIT)
             .filter(|item| item.score.is_some())
+            .take(COMPLETION_LIMIT)
             .map(|item| convert(req, item))
             .enumerate()
             .map(|(i, item)| append_sort_text(item, i))
@@ -80,6 +95,7 @@ async fn complete_all<'a>(req: &'a FeatureRequest<CompletionParams>) -> Vec<Item
     complete_bibtex_commands(req, &mut items).await;
     complete_bibtex_entry_types(req, &mut items).await;
     complete_bibtex_fields(req, &mut items).await;
+    complete_bibtex_field_values(req, &mut items).await;
     complete_latex_arguments(req, &mut items).await;
     complete_latex_begin_command(req, &mut items).await;
     complete_latex_colors(req, &mut items).await;
@@ -89,13 +105,18 @@ async fn complete_all<'a>(req: &'a FeatureRequest<CompletionParams>) -> Vec<Item
     complete_latex_classes(req, &mut items).await;
     complete_latex_packages(req, &mut items).await;
     complete_latex_includes(req, &mut items).await;
+    complete_latex_key_values(req, &mut items).await;
     complete_latex_labels(req, &mut items).await;
     complete_latex_pgf_libraries(req, &mut items).await;
     complete_latex_tikz_libraries(req, &mut items).await;
     complete_latex_component_environments(req, &mut items).await;
+    complete_latex_user_environment_definitions(req, &mut items).await;
     complete_latex_theorem_environments(req, &mut items).await;
     complete_latex_user_environments(req, &mut items).await;
+    complete_latex_user_command_definitions(req, &mut items).await;
+    complete_latex_snippets(req, &mut items).await;
     complete_latex_component_commands(req, &mut items).await;
+    complete_latex_glyphs(req, &mut items).await;
     complete_latex_user_commands(req, &mut items).await;
     items
 }
@@ -104,7 +125,9 @@ fn dedup<'a>(items: Vec<Item<'a>>) -> Vec<Item<'a>> {
     let mut labels = HashSet::new();
     let mut insert = vec![false; items.len()];
     for (i, item) in items.iter().enumerate() {
-        insert[i] = labels.insert(item.data.label());
+        // Snippets may share their prefix with a command and must not hide it.
+        let is_snippet = matches!(item.data, ItemData::Snippet { .. });
+        insert[i] = labels.insert((is_snippet, item.data.label()));
     }
     items
         .into_iter()
@@ -151,15 +174,27 @@ fn score(req: &FeatureRequest<CompletionParams>, items: &mut Vec<Item>) {
         item.score = match &item.data {
             ItemData::ComponentCommand { name, .. } => matcher.fuzzy_match(name, pattern),
             ItemData::ComponentEnvironment { name, .. } => matcher.fuzzy_match(name, pattern),
+            ItemData::Glyph { name, .. } => matcher.fuzzy_match(name, pattern),
             ItemData::UserCommand { name } => matcher.fuzzy_match(name, pattern),
+            ItemData::UserCommandDefinition { name, .. } => matcher
+                .fuzzy_match(name, pattern)
+                .map(|score| score + USER_DEFINITION_BONUS),
             ItemData::UserEnvironment { name } => matcher.fuzzy_match(name, pattern),
-            ItemData::Label { text, .. } => matcher.fuzzy_match(&text, pattern),
+            ItemData::UserEnvironmentDefinition { name, .. } => matcher
+                .fuzzy_match(name, pattern)
+                .map(|score| score + USER_DEFINITION_BONUS),
+            ItemData::Snippet { .. } => matcher.fuzzy_match(item.data.label(), pattern),
+            ItemData::Label { text, bonus, .. } => matcher
+                .fuzzy_match(&text, pattern)
+                .map(|score| score + bonus),
+            ItemData::NewLabel { .. } => Some(0),
             ItemData::Class { name } => matcher.fuzzy_match(&name, pattern),
             ItemData::Package { name } => matcher.fuzzy_match(&name, pattern),
             ItemData::PgfLibrary { name } => matcher.fuzzy_match(name, pattern),
             ItemData::TikzLibrary { name } => matcher.fuzzy_match(name, pattern),
             ItemData::File { name } => matcher.fuzzy_match(name, pattern),
             ItemData::Directory { name } => matcher.fuzzy_match(name, pattern),
+            ItemData::Citation { is_query: true, .. } => Some(0),
             ItemData::Citation { text, .. } => matcher.fuzzy_match(&text, pattern),
             ItemData::Argument { name, .. } => matcher.fuzzy_match(&name, pattern),
             ItemData::BeginCommand => matcher.fuzzy_match("begin", pattern),
@@ -168,7 +203,64 @@ fn score(req: &FeatureRequest<CompletionParams>, items: &mut Vec<Item>) {
             ItemData::GlossaryEntry { name } => matcher.fuzzy_match(name, pattern),
             ItemData::EntryType { ty } => matcher.fuzzy_match(&ty.name, pattern),
             ItemData::Field { field } => matcher.fuzzy_match(&field.name, pattern),
+            ItemData::FieldValue { name, .. } => matcher.fuzzy_match(&name, pattern),
+            ItemData::KeyValueKey {
+                concatenated: true, ..
+            } => Some(0),
+            ItemData::KeyValueKey { key, query, .. } => matcher.fuzzy_match(&key.name, query),
+            ItemData::KeyValueValue { name, query } => matcher.fuzzy_match(name, query),
+        };
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum CommandMode {
+    Math,
+    Text,
+    Symbol,
+    Any,
+}
+
+fn command_mode(data: &ItemData) -> Option<CommandMode> {
+    let (name, is_symbol) = match data {
+        ItemData::ComponentCommand {
+            name, image, glyph, ..
+        } => (*name, image.is_some() || glyph.is_some()),
+        ItemData::Glyph { name, .. } => (*name, true),
+        ItemData::UserCommand { name } => (*name, false),
+        ItemData::UserCommandDefinition { name, is_math, .. } => (*name, *is_math),
+        ItemData::Snippet { snippet } => (snippet.prefix.as_str(), false),
+        _ => return None,
+    };
+
+    let mode = if LANGUAGE_DATA.is_math_command(name) {
+        CommandMode::Math
+    } else if LANGUAGE_DATA.is_text_command(name) {
+        CommandMode::Text
+    } else if is_symbol {
+        CommandMode::Symbol
+    } else {
+        CommandMode::Any
+    };
+    Some(mode)
+}
+
+fn adjust_math_context(req: &FeatureRequest<CompletionParams>, items: &mut Vec<Item>) {
+    let is_math = is_math_context(req);
+    if is_math {
+        items.retain(|item| command_mode(&item.data) != Some(CommandMode::Text));
+    }
+
+    for item in items {
+        let bonus = match (command_mode(&item.data), is_math) {
+            (Some(CommandMode::Math), true) | (Some(CommandMode::Symbol), true) => {
+                MATH_CONTEXT_BONUS
+            }
+            (Some(CommandMode::Text), false) => MATH_CONTEXT_BONUS,
+            (Some(CommandMode::Math), false) => -MATH_CONTEXT_BONUS,
+            _ => 0,
         };
+        item.score = item.score.map(|score| score + bonus);
     }
 }
 
@@ -203,6 +295,18 @@ fn convert(req: &FeatureRequest<CompletionParams>, item: Item) -> CompletionItem
                 ..CompletionItem::new_simple(name.into(), component_detail(file_names))
             }
         }
+        ItemData::Glyph { name, glyph } => {
+            let text_edit = TextEdit::new(item.range, glyph.into());
+            CompletionItem {
+                label: glyph.into(),
+                kind: Some(adjust_kind(req, Structure::Command.completion_kind())),
+                data: Some(CompletionItemData::Command.into()),
+                detail: Some(format!("\\{}", name)),
+                filter_text: Some(format!("\\{}", name)),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                ..CompletionItem::default()
+            }
+        }
         ItemData::UserCommand { name } => {
             let detail = "user-defined".into();
             let text_edit = TextEdit::new(item.range, name.into());
@@ -213,6 +317,49 @@ fn convert(req: &FeatureRequest<CompletionParams>, item: Item) -> CompletionItem
                 ..CompletionItem::new_simple(name.into(), detail)
             }
         }
+        ItemData::UserCommandDefinition {
+            name,
+            snippet,
+            definition,
+            ..
+        } => {
+            let detail = "user-defined".into();
+            let text_edit = TextEdit::new(item.range, snippet);
+            CompletionItem {
+                kind: Some(adjust_kind(req, Structure::Command.completion_kind())),
+                data: Some(CompletionItemData::CommandSnippet.into()),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("```latex\n{}\n```", definition),
+                })),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                insert_text_format: Some(InsertTextFormat::Snippet),
+                ..CompletionItem::new_simple(name.into(), detail)
+            }
+        }
+        ItemData::Snippet { snippet } => {
+            let label = item.data.label();
+            let (body, filter_text) = if snippet.body.starts_with('\\') {
+                (&snippet.body[1..], None)
+            } else {
+                (snippet.body.as_str(), Some(format!("\\{}", label)))
+            };
+            let text_edit = TextEdit::new(item.range, body.into());
+            CompletionItem {
+                label: label.into(),
+                kind: Some(adjust_kind(req, Structure::Snippet.completion_kind())),
+                data: Some(CompletionItemData::CommandSnippet.into()),
+                detail: snippet.description.clone(),
+                filter_text,
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("```latex\n{}\n```", snippet.body),
+                })),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                insert_text_format: Some(InsertTextFormat::Snippet),
+                ..CompletionItem::default()
+            }
+        }
         ItemData::UserEnvironment { name } => {
             let detail = "user-defined".into();
             let text_edit = TextEdit::new(item.range, name.into());
@@ -223,12 +370,27 @@ fn convert(req: &FeatureRequest<CompletionParams>, item: Item) -> CompletionItem
                 ..CompletionItem::new_simple(name.into(), detail)
             }
         }
+        ItemData::UserEnvironmentDefinition { name, definition } => {
+            let detail = "user-defined".into();
+            let text_edit = TextEdit::new(item.range, name.into());
+            CompletionItem {
+                kind: Some(adjust_kind(req, Structure::Environment.completion_kind())),
+                data: Some(CompletionItemData::Environment.into()),
+                documentation: Some(Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("```latex\n{}\n```", definition),
+                })),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                ..CompletionItem::new_simple(name.into(), detail)
+            }
+        }
         ItemData::Label {
             name,
             kind,
             header,
             footer,
             text,
+            ..
         } => {
             let text_edit = TextEdit::new(item.range, name.into());
             CompletionItem {
@@ -243,6 +405,18 @@ fn convert(req: &FeatureRequest<CompletionParams>, item: Item) -> CompletionItem
                 ..CompletionItem::default()
             }
         }
+        ItemData::NewLabel { name, edit } => {
+            let text_edit = TextEdit::new(item.range, name.into());
+            CompletionItem {
+                label: name.into(),
+                kind: Some(adjust_kind(req, Structure::Label.completion_kind())),
+                data: Some(CompletionItemData::Label.into()),
+                detail: Some("Create label in current section".into()),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                additional_text_edits: Some(vec![edit]),
+                ..CompletionItem::default()
+            }
+        }
         ItemData::Class { name } => {
             let text_edit = TextEdit::new(item.range, name.as_ref().into());
             CompletionItem {
@@ -303,13 +477,22 @@ fn convert(req: &FeatureRequest<CompletionParams>, item: Item) -> CompletionItem
                 ..CompletionItem::default()
             }
         }
-        ItemData::Citation { uri, key, text, ty } => {
+        ItemData::Citation {
+            uri,
+            key,
+            text,
+            detail,
+            ty,
+            group,
+            ..
+        } => {
             let text_edit = TextEdit::new(item.range, key.into());
             CompletionItem {
                 label: key.into(),
                 kind: Some(adjust_kind(req, ty.completion_kind())),
-                filter_text: Some(text.clone()),
-                sort_text: Some(text),
+                detail: Some(detail),
+                sort_text: Some(format!("{:0>2} {}", group, text)),
+                filter_text: Some(text),
                 data: Some(
                     CompletionItemData::Citation {
                         uri: uri.clone(),
@@ -400,6 +583,58 @@ fn convert(req: &FeatureRequest<CompletionParams>, item: Item) -> CompletionItem
                 ..CompletionItem::default()
             }
         }
+        ItemData::FieldValue { name, kind, braced } => {
+            let new_text = if braced {
+                format!("{{{}}}", name)
+            } else {
+                name.as_ref().into()
+            };
+            let text_edit = TextEdit::new(item.range, new_text);
+            CompletionItem {
+                label: name.into_owned(),
+                kind: Some(adjust_kind(req, Structure::Argument.completion_kind())),
+                detail: Some(kind.into()),
+                data: Some(CompletionItemData::FieldValue.into()),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                ..CompletionItem::default()
+            }
+        }
+        ItemData::KeyValueKey { key, has_value, .. } => {
+            let (new_text, insert_text_format) = match (key.ty, has_value) {
+                (KeyValueType::Flag, _) | (_, true) => (key.name.clone(), None),
+                (KeyValueType::Boolean, false) | (KeyValueType::Choice, false) => (
+                    format!("{}=${{1|{}|}}", key.name, key.values().join(",")),
+                    Some(InsertTextFormat::Snippet),
+                ),
+                (_, false) => (format!("{}=$1", key.name), Some(InsertTextFormat::Snippet)),
+            };
+            let text_edit = TextEdit::new(item.range, new_text);
+            CompletionItem {
+                label: key.name.clone(),
+                kind: Some(adjust_kind(req, Structure::KeyValue.completion_kind())),
+                data: Some(CompletionItemData::KeyValue.into()),
+                detail: Some(key.detail()),
+                documentation: key.documentation.as_ref().map(|doc| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value: doc.into(),
+                    })
+                }),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                insert_text_format,
+                ..CompletionItem::default()
+            }
+        }
+        ItemData::KeyValueValue { name, .. } => {
+            let text_edit = TextEdit::new(item.range, name.into());
+            CompletionItem {
+                label: name.into(),
+                kind: Some(adjust_kind(req, Structure::KeyValue.completion_kind())),
+                data: Some(CompletionItemData::KeyValue.into()),
+                text_edit: Some(CompletionTextEdit::Edit(text_edit)),
+                ..CompletionItem::default()
+            }
+        }
     };
     new_item.preselect = Some(item.preselect);
     new_item
@@ -417,3 +652,54 @@ fn append_sort_text(mut item: CompletionItem, index: usize) -> CompletionItem {
     };
     item
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{LatexSnippet, Range, RangeExt};
+
+    #[test]
+    fn dedup_snippet_and_command() {
+        let snippet = LatexSnippet {
+            prefix: "item".into(),
+            body: "\\item $0".into(),
+            description: None,
+        };
+        let user_snippet = LatexSnippet {
+            prefix: "\\item".into(),
+            body: "\\item[$1] $0".into(),
+            description: None,
+        };
+        let range = Range::new_simple(0, 1, 0, 4);
+        let items = vec![
+            Item::new(
+                range,
+                ItemData::Snippet {
+                    snippet: &user_snippet,
+                },
+            ),
+            Item::new(range, ItemData::Snippet { snippet: &snippet }),
+            Item::new(
+                range,
+                ItemData::ComponentCommand {
+                    name: "item",
+                    image: None,
+                    glyph: None,
+                    file_names: &[],
+                },
+            ),
+        ];
+
+        let actual_items = dedup(items);
+
+        assert_eq!(actual_items.len(), 2);
+        match actual_items[0].data {
+            ItemData::Snippet { snippet } => assert_eq!(snippet.body, user_snippet.body),
+            _ => panic!("expected the user snippet"),
+        }
+        assert!(matches!(
+            actual_items[1].data,
+            ItemData::ComponentCommand { name: "item", .. }
+        ));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
-    protocol::{Range, Uri},
+    components::KeyValueKey,
+    protocol::{LatexSnippet, Range, TextEdit, Uri},
     syntax::{BibtexEntryTypeDoc, BibtexFieldDoc, Structure},
 };
 use serde::{Deserialize, Serialize};
@@ -36,18 +37,40 @@ pub enum ItemData<'a> {
         name: &'a str,
         file_names: &'a [String],
     },
+    Glyph {
+        name: &'a str,
+        glyph: &'a str,
+    },
     UserCommand {
         name: &'a str,
     },
+    UserCommandDefinition {
+        name: &'a str,
+        snippet: String,
+        definition: String,
+        is_math: bool,
+    },
     UserEnvironment {
         name: &'a str,
     },
+    UserEnvironmentDefinition {
+        name: &'a str,
+        definition: String,
+    },
+    Snippet {
+        snippet: &'a LatexSnippet,
+    },
     Label {
         name: &'a str,
         kind: Structure,
         header: Option<String>,
         footer: Option<String>,
         text: String,
+        bonus: i64,
+    },
+    NewLabel {
+        name: &'a str,
+        edit: TextEdit,
     },
     PgfLibrary {
         name: &'a str,
@@ -71,7 +94,10 @@ pub enum ItemData<'a> {
         uri: &'a Uri,
         key: &'a str,
         text: String,
+        detail: String,
         ty: Structure,
+        group: usize,
+        is_query: bool,
     },
     Argument {
         name: &'a str,
@@ -93,6 +119,21 @@ pub enum ItemData<'a> {
     Field {
         field: &'a BibtexFieldDoc,
     },
+    FieldValue {
+        name: Cow<'a, str>,
+        kind: &'static str,
+        braced: bool,
+    },
+    KeyValueKey {
+        key: &'a KeyValueKey,
+        query: &'a str,
+        has_value: bool,
+        concatenated: bool,
+    },
+    KeyValueValue {
+        name: &'a str,
+        query: &'a str,
+    },
 }
 
 impl<'a> ItemData<'a> {
@@ -100,9 +141,14 @@ impl<'a> ItemData<'a> {
         match self {
             Self::ComponentCommand { name, .. } => name,
             Self::ComponentEnvironment { name, .. } => name,
+            Self::Glyph { glyph, .. } => glyph,
             Self::UserCommand { name } => name,
+            Self::UserCommandDefinition { name, .. } => name,
             Self::UserEnvironment { name } => name,
+            Self::UserEnvironmentDefinition { name, .. } => name,
+            Self::Snippet { snippet } => snippet.prefix.trim_start_matches('\\'),
             Self::Label { name, .. } => name,
+            Self::NewLabel { name, .. } => name,
             Self::Class { name } => &name,
             Self::Package { name } => &name,
             Self::PgfLibrary { name } => name,
@@ -117,6 +163,9 @@ impl<'a> ItemData<'a> {
             Self::GlossaryEntry { name } => name,
             Self::EntryType { ty } => &ty.name,
             Self::Field { field } => &field.name,
+            Self::FieldValue { name, .. } => &name,
+            Self::KeyValueKey { key, .. } => &key.name,
+            Self::KeyValueValue { name, .. } => name,
         }
     }
 }
@@ -138,9 +187,11 @@ pub enum CompletionItemData {
     Class,
     EntryType,
     FieldName,
+    FieldValue,
     Citation { uri: Uri, key: String },
     Argument,
     GlossaryEntry,
+    KeyValue,
 }
 
 impl Into<serde_json::Value> for CompletionItemData {
@@ -2,9 +2,9 @@ use crate::{
     feature::FeatureRequest,
     protocol::{
         CompletionItemKind, CompletionParams, Documentation, MarkupContent, MarkupKind, Position,
-        RangeExt,
+        Range, RangeExt,
     },
-    syntax::{bibtex, latex, SyntaxNode},
+    syntax::{bibtex, latex, SyntaxNode, LANGUAGE_DATA},
     workspace::DocumentContent,
 };
 use std::borrow::Cow;
@@ -65,6 +65,61 @@ fn command_word(cmd: &latex::Command) -> Cow<str> {
     cmd.name.text()[1..].into()
 }
 
+pub fn is_math_context(req: &FeatureRequest<CompletionParams>) -> bool {
+    let pos = req.params.text_document_position.position;
+    let table = match &req.current().content {
+        DocumentContent::Latex(table) => table,
+        DocumentContent::Bibtex(_) => return false,
+    };
+
+    let equations = table.equations.iter().map(|eq| eq.range(table));
+    let environments = table
+        .environments
+        .iter()
+        .filter(|env| env.left.is_math(table))
+        .map(|env| env.range(table));
+    let inlines = table.inlines.iter().map(|inline| inline.range(table));
+    let unclosed_inline = table
+        .nodes()
+        .into_iter()
+        .filter(|node| table.as_math(*node).is_some())
+        .map(|node| table[node].start())
+        .max()
+        .filter(|start| {
+            table
+                .inlines
+                .iter()
+                .all(|inline| inline.range(table).end <= *start)
+        })
+        .map(|start| Range::new(start, Position::new(u64::MAX, 0)));
+    let math_start = equations
+        .chain(environments)
+        .chain(inlines)
+        .chain(unclosed_inline)
+        .filter(|range| range.contains_exclusive(pos))
+        .map(|range| range.start)
+        .max();
+
+    let text_start = table
+        .commands
+        .iter()
+        .filter(|node| {
+            let cmd = table.as_command(**node).unwrap();
+            LANGUAGE_DATA.is_text_mode_command(&cmd.name.text()[1..])
+        })
+        .filter_map(|node| table.extract_group(*node, latex::GroupKind::Group, 0))
+        .map(|group| table[group].range())
+        .filter(|range| range.contains_exclusive(pos))
+        .map(|range| range.start)
+        .max();
+
+    match (math_start, text_start) {
+        (Some(math_start), Some(text_start)) => math_start > text_start,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
 pub fn component_detail(file_names: &[String]) -> String {
     if file_names.is_empty() {
         "built-in".to_owned()
@@ -119,3 +174,73 @@ pub fn adjust_kind(
     }
     CompletionItemKind::Text
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feature::FeatureTester;
+    use indoc::indoc;
+
+    async fn run_math_context(line: u64, character: u64) -> bool {
+        let req = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        $\alpha$ \foo
+                        \[ \bar \]
+                        \begin{align}
+                            \baz
+                        \end{align}
+                        $\qux
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(line, character)
+            .test_completion_request()
+            .await;
+
+        is_math_context(&req)
+    }
+
+    #[tokio::test]
+    async fn math_context_inline() {
+        assert!(run_math_context(0, 3).await);
+        assert!(!run_math_context(0, 12).await);
+    }
+
+    #[tokio::test]
+    async fn math_context_equation() {
+        assert!(run_math_context(1, 5).await);
+    }
+
+    #[tokio::test]
+    async fn math_context_environment() {
+        assert!(run_math_context(3, 6).await);
+    }
+
+    #[tokio::test]
+    async fn math_context_unclosed_inline() {
+        assert!(run_math_context(5, 4).await);
+    }
+
+    #[tokio::test]
+    async fn math_context_text_group() {
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\[ \text{ \foo $\bar$ } \]"#)
+            .main("main.tex")
+            .position(0, 13)
+            .test_completion_request()
+            .await;
+        assert!(!is_math_context(&req));
+
+        let req = FeatureTester::new()
+            .file("main.tex", r#"\[ \text{ \foo $\bar$ } \]"#)
+            .main("main.tex")
+            .position(0, 19)
+            .test_completion_request()
+            .await;
+        assert!(is_math_context(&req));
+    }
+}
@@ -0,0 +1,378 @@
+use crate::{
+    components::{Command, Component},
+    tex::Resolver,
+};
+use log::{debug, warn};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::SystemTime,
+};
+use tokio::fs;
+
+static COMMAND_DEFINITION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        "\\\\(?:(?:new|renew|provide)command\\*?|DeclareRobustCommand\\*?|(?:New|Renew|Provide|Declare)DocumentCommand|DeclareMathOperator\\*?|[gex]?def|let)\\s*\\{?\\s*\\\\(?P<name>[A-Za-z@]+)",
+    )
+    .unwrap()
+});
+
+static ENVIRONMENT_DEFINITION_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        "\\\\(?:(?:new|renew|provide)environment\\*?|(?:New|Renew|Provide|Declare)DocumentEnvironment)\\s*\\{(?P<name>[A-Za-z@*]+)\\}",
+    )
+    .unwrap()
+});
+
+static REQUIRE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        "\\\\(?P<kind>RequirePackage|RequirePackageWithOptions|LoadClass|LoadClassWithOptions)\\s*(?:\\[[^\\]]*\\])?\\s*\\{(?P<names>[^}]*)\\}",
+    )
+    .unwrap()
+});
+
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    path: PathBuf,
+    modified: SystemTime,
+    component: Arc<Component>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+struct IndexCache {
+    version: u32,
+    entries: Vec<IndexEntry>,
+}
+
+pub type LocalComponents = Arc<HashMap<String, Arc<Component>>>;
+
+#[derive(Debug, Clone)]
+struct IndexRequest {
+    resolver: Arc<Resolver>,
+    file_names: HashSet<String>,
+}
+
+impl IndexRequest {
+    fn is_same(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.resolver, &other.resolver) && self.file_names == other.file_names
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ComponentIndex {
+    entries: RwLock<HashMap<String, IndexEntry>>,
+    components: RwLock<LocalComponents>,
+    last_request: Mutex<Option<IndexRequest>>,
+    pending_request: Mutex<Option<IndexRequest>>,
+    is_loaded: AtomicBool,
+    is_indexing: AtomicBool,
+}
+
+impl ComponentIndex {
+    pub fn components(&self) -> LocalComponents {
+        Arc::clone(&self.components.read().unwrap())
+    }
+
+    /// Indexes the given packages and classes including their dependencies.
+    ///
+    /// Nothing happens if neither the file names nor the resolver changed since the last call.
+    /// Requests that arrive while indexing are queued and processed afterwards.
+    pub async fn update(
+        &self,
+        resolver: Arc<Resolver>,
+        file_names: HashSet<String>,
+        cache_path: Option<&Path>,
+    ) -> bool {
+        let request = IndexRequest {
+            resolver,
+            file_names,
+        };
+
+        {
+            let mut last_request = self.last_request.lock().unwrap();
+            if last_request
+                .as_ref()
+                .map_or(false, |last| last.is_same(&request))
+            {
+                return false;
+            }
+            *last_request = Some(request.clone());
+        }
+
+        *self.pending_request.lock().unwrap() = Some(request);
+        let mut has_changed = false;
+        while !self.is_indexing.swap(true, Ordering::SeqCst) {
+            loop {
+                let request = self.pending_request.lock().unwrap().take();
+                match request {
+                    Some(request) => has_changed |= self.index(request, cache_path).await,
+                    None => break,
+                }
+            }
+
+            self.is_indexing.store(false, Ordering::SeqCst);
+            if self.pending_request.lock().unwrap().is_none() {
+                break;
+            }
+        }
+        has_changed
+    }
+
+    async fn index(&self, request: IndexRequest, cache_path: Option<&Path>) -> bool {
+        if !self.is_loaded.swap(true, Ordering::SeqCst) {
+            self.load_cache(cache_path).await;
+        }
+
+        let IndexRequest {
+            resolver,
+            file_names,
+        } = request;
+
+        let mut has_changed = false;
+        let mut visited = HashSet::new();
+        let mut queue: VecDeque<_> = file_names.into_iter().collect();
+        while let Some(file_name) = queue.pop_front() {
+            if !visited.insert(file_name.clone()) {
+                continue;
+            }
+
+            let path = match resolver.files_by_name.get(&file_name) {
+                Some(path) => path,
+                None => continue,
+            };
+
+            let modified = match fs::metadata(path).await.and_then(|meta| meta.modified()) {
+                Ok(modified) => modified,
+                Err(_) => continue,
+            };
+
+            let cached_entry = self.entries.read().unwrap().get(&file_name).cloned();
+            let entry = match cached_entry {
+                Some(entry) if entry.path == *path && entry.modified == modified => entry,
+                _ => match fs::read(path).await {
+                    Ok(bytes) => {
+                        debug!("Indexing {}", path.to_string_lossy());
+                        let text = String::from_utf8_lossy(&bytes);
+                        let component = Arc::new(analyze(&file_name, &text));
+                        let entry = IndexEntry {
+                            path: path.clone(),
+                            modified,
+                            component,
+                        };
+                        self.entries
+                            .write()
+                            .unwrap()
+                            .insert(file_name.clone(), entry.clone());
+                        has_changed = true;
+                        entry
+                    }
+                    Err(why) => {
+                        warn!("Unable to read {}: {}", path.to_string_lossy(), why);
+                        continue;
+                    }
+                },
+            };
+
+            queue.extend(entry.component.references.iter().cloned());
+        }
+
+        if has_changed {
+            let components = self
+                .entries
+                .read()
+                .unwrap()
+                .iter()
+                .map(|(file_name, entry)| (file_name.clone(), Arc::clone(&entry.component)))
+                .collect();
+            *self.components.write().unwrap() = Arc::new(components);
+            self.save_cache(cache_path).await;
+        }
+        has_changed
+    }
+
+    async fn load_cache(&self, path: Option<&Path>) {
+        let path = match path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let cache: IndexCache = match fs::read(path).await {
+            Ok(bytes) => match serde_json::from_slice(&bytes) {
+                Ok(cache) => cache,
+                Err(_) => return,
+            },
+            Err(_) => return,
+        };
+
+        if cache.version != CACHE_VERSION {
+            return;
+        }
+
+        let mut entries = self.entries.write().unwrap();
+        for entry in cache.entries {
+            if let Some(file_name) = entry.component.file_names.first() {
+                entries.insert(file_name.clone(), entry);
+            }
+        }
+    }
+
+    async fn save_cache(&self, path: Option<&Path>) {
+        let path = match path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let cache = IndexCache {
+            version: CACHE_VERSION,
+            entries: self.entries.read().unwrap().values().cloned().collect(),
+        };
+
+        let result = async {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            fs::write(path, serde_json::to_vec(&cache)?).await
+        };
+
+        if let Err(why) = result.await {
+            warn!("Unable to write component cache: {}", why);
+        }
+    }
+}
+
+pub fn analyze(file_name: &str, text: &str) -> Component {
+    let mut commands: Vec<Command> = Vec::new();
+    for captures in COMMAND_DEFINITION_REGEX.captures_iter(text) {
+        let name = &captures["name"];
+        if !name.contains('@') && commands.iter().all(|cmd| cmd.name != name) {
+            commands.push(Command {
+                name: name.into(),
+                image: None,
+                glyph: None,
+                parameters: Vec::new(),
+            });
+        }
+    }
+
+    let mut environments: Vec<String> = Vec::new();
+    for captures in ENVIRONMENT_DEFINITION_REGEX.captures_iter(text) {
+        let name = &captures["name"];
+        if !name.contains('@') && environments.iter().all(|env| env != name) {
+            environments.push(name.into());
+        }
+    }
+
+    let mut references: Vec<String> = Vec::new();
+    for captures in REQUIRE_REGEX.captures_iter(text) {
+        let extension = if captures["kind"].starts_with("LoadClass") {
+            "cls"
+        } else {
+            "sty"
+        };
+
+        for name in captures["names"].split(',').map(str::trim) {
+            let reference = format!("{}.{}", name, extension);
+            if !name.is_empty() && !references.contains(&reference) {
+                references.push(reference);
+            }
+        }
+    }
+
+    Component {
+        file_names: vec![file_name.into()],
+        references,
+        commands,
+        environments,
+    }
+}
+
+pub static COMPONENT_INDEX: Lazy<ComponentIndex> = Lazy::new(ComponentIndex::default);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn analyze_package() {
+        let component = analyze(
+            "foo.sty",
+            indoc!(
+                r#"
+                    \NeedsTeXFormat{LaTeX2e}
+                    \RequirePackage[utf8]{inputenc}
+                    \RequirePackage{amsmath, amssymb}
+                    \newcommand{\foo}[1]{#1}
+                    \newcommand*\bar{bar}
+                    \def\baz{baz}
+                    \def\foo@internal{}
+                    \NewDocumentCommand{\qux}{m}{#1}
+                    \newenvironment{foobar}{}{}
+                    \newenvironment{foo@env}{}{}
+                "#
+            ),
+        );
+
+        let command_names: Vec<_> = component
+            .commands
+            .iter()
+            .map(|cmd| cmd.name.as_str())
+            .collect();
+
+        assert_eq!(component.file_names, vec!["foo.sty"]);
+        assert_eq!(
+            component.references,
+            vec!["inputenc.sty", "amsmath.sty", "amssymb.sty"]
+        );
+        assert_eq!(command_names, vec!["foo", "bar", "baz", "qux"]);
+        assert_eq!(component.environments, vec!["foobar"]);
+    }
+
+    #[test]
+    fn analyze_class() {
+        let component = analyze(
+            "thesis.cls",
+            indoc!(
+                r#"
+                    \LoadClass[a4paper]{report}
+                    \newcommand{\advisor}[1]{}
+                "#
+            ),
+        );
+
+        assert_eq!(component.references, vec!["report.cls"]);
+        assert_eq!(component.commands[0].name, "advisor");
+    }
+
+    #[tokio::test]
+    async fn update_skips_unchanged_request() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("foo.sty");
+        std::fs::write(&path, "\\newcommand{\\foo}{}").unwrap();
+        let mut files_by_name = HashMap::new();
+        files_by_name.insert("foo.sty".to_owned(), path);
+        let resolver = Arc::new(Resolver::new(files_by_name));
+        let file_names: HashSet<_> = vec!["foo.sty".to_owned()].into_iter().collect();
+        let cache_path = dir.path().join("components.json");
+        let index = ComponentIndex::default();
+
+        assert!(
+            index
+                .update(Arc::clone(&resolver), file_names.clone(), Some(&cache_path))
+                .await
+        );
+        assert!(index.components().contains_key("foo.sty"));
+        assert!(cache_path.is_file());
+        assert!(!index.update(resolver, file_names, Some(&cache_path)).await);
+    }
+}
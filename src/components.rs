@@ -7,6 +7,8 @@ use serde::{Deserialize, Serialize};
 pub struct Database {
     pub components: Vec<Component>,
     pub metadata: Vec<Metadata>,
+    #[serde(default)]
+    pub key_value_targets: Vec<KeyValueTarget>,
 }
 
 impl Database {
@@ -38,6 +40,17 @@ impl Database {
             .any(|component| component.file_names.iter().any(|f| f == file_name))
     }
 
+    pub fn key_value_target(
+        &self,
+        kind: KeyValueTargetKind,
+        name: &str,
+        braced: bool,
+    ) -> Option<&KeyValueTarget> {
+        self.key_value_targets.iter().find(|target| {
+            target.kind == kind && target.braced == braced && target.names.iter().any(|n| n == name)
+        })
+    }
+
     pub fn documentation(&self, name: &str) -> Option<MarkupContent> {
         let metadata = self
             .metadata
@@ -89,6 +102,82 @@ pub struct Metadata {
     pub description: Option<String>,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyValueTargetKind {
+    Package,
+    Class,
+    Command,
+    Environment,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeyValueType {
+    Flag,
+    Boolean,
+    Length,
+    Number,
+    String,
+    Choice,
+    Color,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyValueKey {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: KeyValueType,
+    #[serde(default)]
+    pub values: Vec<String>,
+    pub documentation: Option<String>,
+}
+
+impl KeyValueKey {
+    pub fn values(&self) -> Vec<&str> {
+        match self.ty {
+            KeyValueType::Boolean => vec!["true", "false"],
+            _ => self.values.iter().map(String::as_str).collect(),
+        }
+    }
+
+    pub fn detail(&self) -> String {
+        match self.ty {
+            KeyValueType::Flag => "flag".into(),
+            KeyValueType::Boolean | KeyValueType::Choice => self.values().join(" | "),
+            KeyValueType::Length => "length".into(),
+            KeyValueType::Number => "number".into(),
+            KeyValueType::String => "string".into(),
+            KeyValueType::Color => "color".into(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyValueTarget {
+    pub kind: KeyValueTargetKind,
+    pub names: Vec<String>,
+    #[serde(default)]
+    pub braced: bool,
+    #[serde(default)]
+    pub concatenated: bool,
+    pub keys: Vec<KeyValueKey>,
+}
+
+impl KeyValueTarget {
+    pub fn find(&self, name: &str) -> Option<&KeyValueKey> {
+        self.keys.iter().find(|key| key.name == name)
+    }
+}
+
 const JSON: &str = include_str!("../data/components.json");
 
-pub static COMPONENT_DATABASE: Lazy<Database> = Lazy::new(|| serde_json::from_str(JSON).unwrap());
+const KEY_VALUE_JSON: &str = include_str!("../data/key_values.json");
+
+pub static COMPONENT_DATABASE: Lazy<Database> = Lazy::new(|| {
+    let mut database: Database = serde_json::from_str(JSON).unwrap();
+    database.key_value_targets = serde_json::from_str(KEY_VALUE_JSON).unwrap();
+    database
+});
@@ -2,28 +2,58 @@ use crate::protocol::*;
 use futures::lock::Mutex;
 use log::{error, warn};
 use serde::de::DeserializeOwned;
-use std::sync::Arc;
+use std::{iter, path::PathBuf, sync::Arc};
+
+const SECTIONS: &[&str] = &["latex", "bibtex"];
 
 #[derive(Debug)]
 pub struct ConfigManager<C> {
     client: Arc<C>,
     client_capabilities: Arc<ClientCapabilities>,
-    options: Mutex<Options>,
+    options: Mutex<ScopedOptions>,
 }
 
 impl<C: LspClient + Send + Sync + 'static> ConfigManager<C> {
-    pub fn new(client: Arc<C>, client_capabilities: Arc<ClientCapabilities>) -> Self {
+    pub fn new(
+        client: Arc<C>,
+        client_capabilities: Arc<ClientCapabilities>,
+        current_dir: Arc<PathBuf>,
+    ) -> Self {
         Self {
             client,
             client_capabilities,
-            options: Mutex::default(),
+            options: Mutex::new(ScopedOptions::new(Options::default(), current_dir)),
         }
     }
 
-    pub async fn get(&self) -> Options {
+    pub async fn get(&self) -> ScopedOptions {
         self.options.lock().await.clone()
     }
 
+    pub async fn update_folders(&self, added: Vec<Uri>, removed: Vec<Uri>) {
+        let mut options = self.options.lock().await;
+        options
+            .folders
+            .retain(|folder| !removed.contains(&folder.uri));
+        for uri in added {
+            if options.folders.iter().any(|folder| folder.uri == uri) {
+                continue;
+            }
+
+            match uri.to_file_path() {
+                Ok(path) => {
+                    let folder = FolderOptions {
+                        uri,
+                        path,
+                        options: None,
+                    };
+                    options.folders.push(folder);
+                }
+                Err(()) => warn!("Unsupported workspace folder: {}", uri),
+            }
+        }
+    }
+
     pub async fn register(&self) {
         if !self.client_capabilities.has_pull_configuration_support()
             && self.client_capabilities.has_push_configuration_support()
@@ -48,8 +78,9 @@ impl<C: LspClient + Send + Sync + 'static> ConfigManager<C> {
 
     pub async fn push(&self, options: serde_json::Value) {
         match serde_json::from_value(options) {
-            Ok(options) => {
-                *self.options.lock().await = options;
+            Ok(new_options) => {
+                let mut options = self.options.lock().await;
+                options.default = new_options;
             }
             Err(why) => {
                 error!("Invalid configuration: {}", why);
@@ -58,46 +89,78 @@ impl<C: LspClient + Send + Sync + 'static> ConfigManager<C> {
     }
 
     pub async fn pull(&self) -> bool {
-        if self.client_capabilities.has_pull_configuration_support() {
-            let latex = self.pull_section("latex").await;
-            let bibtex = self.pull_section("bibtex").await;
-
-            let new_options = Options {
-                latex: Some(latex),
-                bibtex: Some(bibtex),
-            };
-            let mut old_options = self.options.lock().await;
-            let has_changed = *old_options != new_options;
-            *old_options = new_options;
-            has_changed
-        } else {
-            false
+        if !self.client_capabilities.has_pull_configuration_support() {
+            return false;
         }
-    }
 
-    async fn pull_section<T: DeserializeOwned + Default>(&self, section: &str) -> T {
-        let params = ConfigurationParams {
-            items: vec![ConfigurationItem {
-                section: Some(section.into()),
-                scope_uri: None,
-            }],
+        let folder_uris: Vec<_> = {
+            let options = self.options.lock().await;
+            options
+                .folders
+                .iter()
+                .map(|folder| folder.uri.clone())
+                .collect()
         };
 
-        match self.client.configuration(params).await {
-            Ok(json) => match serde_json::from_value::<Vec<T>>(json) {
-                Ok(config) => config.into_iter().next().unwrap(),
-                Err(_) => {
-                    warn!("Invalid configuration: {}", section);
-                    T::default()
-                }
-            },
+        let scope_uris: Vec<_> = iter::once(None)
+            .chain(folder_uris.iter().map(Some))
+            .collect();
+
+        let items = scope_uris
+            .iter()
+            .flat_map(|scope_uri| {
+                SECTIONS.iter().map(move |section| ConfigurationItem {
+                    section: Some((*section).into()),
+                    scope_uri: scope_uri.map(|uri| uri.as_str().to_owned()),
+                })
+            })
+            .collect();
+
+        let values = match self
+            .client
+            .configuration(ConfigurationParams { items })
+            .await
+        {
+            Ok(json) => serde_json::from_value::<Vec<serde_json::Value>>(json).unwrap_or_default(),
             Err(why) => {
-                error!(
-                    "Retrieving configuration for {} failed: {}",
-                    section, why.message
-                );
+                error!("Retrieving configuration failed: {}", why.message);
+                Vec::new()
+            }
+        };
+
+        let mut values = values.into_iter();
+        let mut next_options = || Options {
+            latex: Some(Self::parse_section("latex", values.next())),
+            bibtex: Some(Self::parse_section("bibtex", values.next())),
+        };
+
+        let default = next_options();
+        let folder_options: Vec<_> = folder_uris.iter().map(|_| next_options()).collect();
+
+        let mut options = self.options.lock().await;
+        let mut has_changed = options.default != default;
+        options.default = default;
+        for (uri, new_options) in folder_uris.into_iter().zip(folder_options) {
+            if let Some(folder) = options.folders.iter_mut().find(|folder| folder.uri == uri) {
+                let new_options = Some(new_options);
+                has_changed |= folder.options != new_options;
+                folder.options = new_options;
+            }
+        }
+        has_changed
+    }
+
+    fn parse_section<T: DeserializeOwned + Default>(
+        section: &str,
+        value: Option<serde_json::Value>,
+    ) -> T {
+        match value.map(serde_json::from_value) {
+            Some(Ok(config)) => config,
+            Some(Err(_)) => {
+                warn!("Invalid configuration: {}", section);
                 T::default()
             }
+            None => T::default(),
         }
     }
 }
@@ -0,0 +1,144 @@
+use crate::{
+    feature::{FeatureProvider, FeatureRequest},
+    protocol::{LocationLink, TextDocumentPositionParams},
+    syntax::SyntaxNode,
+    workspace::DocumentContent,
+};
+use async_trait::async_trait;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LatexEnvironmentDefinitionProvider;
+
+#[async_trait]
+impl FeatureProvider for LatexEnvironmentDefinitionProvider {
+    type Params = TextDocumentPositionParams;
+    type Output = Vec<LocationLink>;
+
+    async fn execute<'a>(&'a self, req: &'a FeatureRequest<Self::Params>) -> Self::Output {
+        let mut links = Vec::new();
+        let table = match &req.current().content {
+            DocumentContent::Latex(table) => table,
+            DocumentContent::Bibtex(_) => return links,
+        };
+
+        let name = match table.find_environment_name(req.params.position) {
+            Some(name) => name,
+            None => return links,
+        };
+
+        for doc in req.related() {
+            if let DocumentContent::Latex(table) = &doc.content {
+                let env_defs = table
+                    .environment_definitions
+                    .iter()
+                    .map(|def| (def.parent, def.name(&table)));
+                let thm_defs = table
+                    .theorem_definitions
+                    .iter()
+                    .map(|def| (def.parent, def.name(&table)));
+
+                for (parent, def_name) in env_defs.chain(thm_defs) {
+                    if def_name.text() == name.text() {
+                        links.push(LocationLink {
+                            origin_selection_range: Some(name.range()),
+                            target_uri: doc.uri.clone().into(),
+                            target_range: table[parent].range(),
+                            target_selection_range: def_name.range(),
+                        });
+                    }
+                }
+            }
+        }
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        feature::FeatureTester,
+        protocol::{Range, RangeExt},
+    };
+    use indoc::indoc;
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let actual_links = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_position(LatexEnvironmentDefinitionProvider)
+            .await;
+
+        assert!(actual_links.is_empty());
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let actual_links = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .position(0, 0)
+            .test_position(LatexEnvironmentDefinitionProvider)
+            .await;
+
+        assert!(actual_links.is_empty());
+    }
+
+    #[tokio::test]
+    async fn environment_definition() {
+        let actual_links = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \include{bar}
+                        \begin{note}\end{note}
+                    "#
+                ),
+            )
+            .file("bar.tex", r#"\NewDocumentEnvironment{note}{}{}{}"#)
+            .file("baz.tex", r#"\newenvironment{note}{}{}"#)
+            .main("foo.tex")
+            .position(1, 19)
+            .test_position(LatexEnvironmentDefinitionProvider)
+            .await;
+
+        let expected_links = vec![LocationLink {
+            origin_selection_range: Some(Range::new_simple(1, 17, 1, 21)),
+            target_uri: FeatureTester::uri("bar.tex").into(),
+            target_range: Range::new_simple(0, 0, 0, 35),
+            target_selection_range: Range::new_simple(0, 24, 0, 28),
+        }];
+
+        assert_eq!(actual_links, expected_links);
+    }
+
+    #[tokio::test]
+    async fn theorem_definition() {
+        let actual_links = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \newtheorem{lemma}{Lemma}
+                        \begin{lemma}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 8)
+            .test_position(LatexEnvironmentDefinitionProvider)
+            .await;
+
+        let expected_links = vec![LocationLink {
+            origin_selection_range: Some(Range::new_simple(1, 7, 1, 12)),
+            target_uri: FeatureTester::uri("main.tex").into(),
+            target_range: Range::new_simple(0, 0, 0, 25),
+            target_selection_range: Range::new_simple(0, 12, 0, 17),
+        }];
+
+        assert_eq!(actual_links, expected_links);
+    }
+}
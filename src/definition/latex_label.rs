@@ -1,13 +1,13 @@
 use crate::{
     feature::{DocumentView, FeatureProvider, FeatureRequest},
     outline::{Outline, OutlineContext, OutlineContextItem},
-    protocol::{LocationLink, Options, RangeExt, TextDocumentPositionParams},
+    protocol::{LocationLink, RangeExt, TextDocumentPositionParams},
     symbol::build_section_tree,
     syntax::{latex, LatexLabelKind, SyntaxNode},
     workspace::DocumentContent,
 };
 use async_trait::async_trait;
-use std::{path::Path, sync::Arc};
+use std::sync::Arc;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
 pub struct LatexLabelDefinitionProvider;
@@ -22,19 +22,8 @@ impl FeatureProvider for LatexLabelDefinitionProvider {
         if let Some(reference) = Self::find_reference(req) {
             for doc in req.related() {
                 let snapshot = Arc::clone(&req.view.snapshot);
-                let view = DocumentView::analyze(
-                    snapshot,
-                    Arc::clone(&doc),
-                    &req.options,
-                    &req.current_dir,
-                );
-                Self::find_definitions(
-                    &view,
-                    &req.options,
-                    &req.current_dir,
-                    &reference,
-                    &mut links,
-                );
+                let view = DocumentView::analyze(snapshot, Arc::clone(&doc));
+                Self::find_definitions(&view, &reference, &mut links);
             }
         }
         links
@@ -56,14 +45,12 @@ impl LatexLabelDefinitionProvider {
 
     fn find_definitions(
         view: &DocumentView,
-        options: &Options,
-        current_dir: &Path,
         reference: &latex::Token,
         links: &mut Vec<LocationLink>,
     ) {
         if let DocumentContent::Latex(table) = &view.current.content {
-            let outline = Outline::analyze(view, options, current_dir);
-            let section_tree = build_section_tree(view, table, options, current_dir);
+            let outline = Outline::analyze(view);
+            let section_tree = build_section_tree(view, table);
             for label in &table.labels {
                 if label.kind == LatexLabelKind::Definition {
                     let context = OutlineContext::parse(view, &outline, *label);
@@ -1,11 +1,13 @@
 mod bibtex_string;
 mod latex_citation;
 mod latex_cmd;
+mod latex_env;
 mod latex_label;
 
 use self::{
     bibtex_string::BibtexStringDefinitionProvider, latex_citation::LatexCitationDefinitionProvider,
-    latex_cmd::LatexCommandDefinitionProvider, latex_label::LatexLabelDefinitionProvider,
+    latex_cmd::LatexCommandDefinitionProvider, latex_env::LatexEnvironmentDefinitionProvider,
+    latex_label::LatexLabelDefinitionProvider,
 };
 use crate::{
     feature::{ConcatProvider, FeatureProvider, FeatureRequest},
@@ -24,6 +26,7 @@ impl DefinitionProvider {
                 Box::new(BibtexStringDefinitionProvider),
                 Box::new(LatexCitationDefinitionProvider),
                 Box::new(LatexCommandDefinitionProvider),
+                Box::new(LatexEnvironmentDefinitionProvider),
                 Box::new(LatexLabelDefinitionProvider),
             ]),
         }
@@ -1,5 +1,8 @@
 use crate::{
-    protocol::{Diagnostic, DiagnosticSeverity, Options, Position, Range, Uri},
+    protocol::{
+        BuildLog, BuildLogEntry, Diagnostic, DiagnosticSeverity, Location, Options, Position,
+        Range, Uri,
+    },
     workspace::{Document, Snapshot},
 };
 use futures::lock::Mutex;
@@ -35,6 +38,10 @@ impl BuildDiagnosticsProvider {
         }
     }
 
+    pub async fn remove(&self, uri: &Uri) {
+        self.diagnostics_by_uri.lock().await.remove(uri);
+    }
+
     pub async fn update(
         &self,
         snapshot: &Snapshot,
@@ -133,6 +140,71 @@ impl Into<Diagnostic> for BuildError {
     }
 }
 
+pub async fn build_log(
+    snapshot: &Snapshot,
+    tex_uri: &Uri,
+    options: &Options,
+    current_dir: &Path,
+) -> Option<BuildLog> {
+    let root_uri = snapshot
+        .parent(tex_uri)
+        .map(|doc| doc.uri.clone())
+        .unwrap_or_else(|| tex_uri.clone());
+
+    let log_path = snapshot
+        .resolve_aux_targets(&root_uri, options, current_dir, "log")?
+        .into_iter()
+        .filter(|uri| uri.scheme() == "file")
+        .filter_map(|uri| uri.to_file_path().ok())
+        .find(|path| path.exists())?;
+
+    let log = String::from_utf8_lossy(&fs::read(&log_path).await.ok()?).into_owned();
+    let entries = parse_build_log_entries(&root_uri, &log);
+    Some(BuildLog {
+        uri: Uri::from_file_path(log_path).ok()?.into(),
+        text: log,
+        entries,
+    })
+}
+
+fn parse_build_log_entries(uri: &Uri, log: &str) -> Vec<BuildLogEntry> {
+    let prepared = prepare_log(log);
+    let mut matches = parse_build_log_matches(uri, &prepared.text);
+    matches.sort_by_key(|result| result.start);
+    matches
+        .into_iter()
+        .map(|result| {
+            let severity = match result.error.kind {
+                BuildErrorKind::Error => DiagnosticSeverity::Error,
+                BuildErrorKind::Warning => DiagnosticSeverity::Warning,
+            };
+            let uri = result.error.uri;
+            let location = result.error.line.map(|line| {
+                let position = Position::new(line, 0);
+                Location::new(uri.into(), Range::new(position, position))
+            });
+            let start = prepared.original_offset(result.start);
+            let end = prepared.original_offset(result.end).max(start);
+            BuildLogEntry {
+                severity,
+                message: result.error.message,
+                location,
+                files: result.files.into_iter().map(Into::into).collect(),
+                span: Range::new(offset_to_position(log, start), offset_to_position(log, end)),
+                raw: log[start..end].to_owned(),
+            }
+        })
+        .collect()
+}
+
+fn offset_to_position(text: &str, offset: usize) -> Position {
+    let prefix = &text[..offset];
+    let line = prefix.matches('\n').count() as u64;
+    let line_start = prefix.rfind('\n').map(|index| index + 1).unwrap_or(0);
+    let character = prefix[line_start..].chars().count() as u64;
+    Position::new(line, character)
+}
+
 const MAX_LINE_LENGTH: usize = 79;
 
 pub static PACKAGE_MESSAGE_REGEX: Lazy<Regex> =
@@ -153,7 +225,21 @@ pub static BAD_BOX_REGEX: Lazy<Regex> = Lazy::new(|| {
 });
 
 fn parse_build_log(uri: &Uri, log: &str) -> Vec<BuildError> {
-    let log = prepare_log(log);
+    parse_build_log_matches(uri, &prepare_log(log).text)
+        .into_iter()
+        .map(|result| result.error)
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct BuildLogMatch {
+    error: BuildError,
+    files: Vec<Uri>,
+    start: usize,
+    end: usize,
+}
+
+fn parse_build_log_matches(uri: &Uri, log: &str) -> Vec<BuildLogMatch> {
     let mut ranges: Vec<FileRange> = FILE_REGEX
         .find_iter(&log)
         .map(|result| create_file_range(uri.clone(), &log, result))
@@ -173,8 +259,8 @@ fn extract_matches(
     ranges: &[FileRange],
     regex: &Regex,
     kind: BuildErrorKind,
-) -> Vec<BuildError> {
-    let mut errors = Vec::new();
+) -> Vec<BuildLogMatch> {
+    let mut matches = Vec::new();
     for result in regex.find_iter(&log) {
         let captures = regex.captures(&log[result.start()..result.end()]).unwrap();
         let message = captures
@@ -194,40 +280,99 @@ fn extract_matches(
                 .map(|result| u64::from_str_radix(result.as_str(), 10).unwrap() - 1);
 
             let uri = range.uri.as_ref().unwrap_or(parent_uri);
-            errors.push(BuildError::new(uri.clone(), kind, message, line));
+            let files = ranges
+                .iter()
+                .rev()
+                .filter(|range| range.contains(result.start()))
+                .filter_map(|range| range.uri.clone())
+                .collect();
+
+            matches.push(BuildLogMatch {
+                error: BuildError::new(uri.clone(), kind, message, line),
+                files,
+                start: result.start(),
+                end: result.end(),
+            });
+        }
+    }
+    matches
+}
+
+/// The log with its wrapped lines joined together.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+struct PreparedLog {
+    text: String,
+    segments: Vec<LogSegment>,
+}
+
+/// A part of the prepared log that has been copied from the original log.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct LogSegment {
+    offset: usize,
+    original_offset: usize,
+}
+
+impl PreparedLog {
+    fn push_line(&mut self, original_offset: usize, text: &str) {
+        if !self.segments.is_empty() {
+            self.text.push('\n');
         }
+        self.push_segment(original_offset, text);
+    }
+
+    fn push_segment(&mut self, original_offset: usize, text: &str) {
+        self.segments.push(LogSegment {
+            offset: self.text.len(),
+            original_offset,
+        });
+        self.text.push_str(text);
+    }
+
+    fn original_offset(&self, offset: usize) -> usize {
+        let index = match self
+            .segments
+            .binary_search_by_key(&offset, |segment| segment.offset)
+        {
+            Ok(index) => index,
+            Err(0) => return 0,
+            Err(index) => index - 1,
+        };
+        let segment = self.segments[index];
+        let end = self
+            .segments
+            .get(index + 1)
+            .map_or(usize::max_value(), |next| next.original_offset);
+        (segment.original_offset + offset - segment.offset).min(end)
     }
-    errors
 }
 
-fn prepare_log(log: &str) -> String {
+fn prepare_log(log: &str) -> PreparedLog {
+    let line_offset = |line: &str| line.as_ptr() as usize - log.as_ptr() as usize;
     let mut old_lines = log.lines();
-    let mut new_lines: Vec<String> = Vec::new();
+    let mut prepared = PreparedLog::default();
     while let Some(line) = old_lines.next() {
-        if PACKAGE_MESSAGE_REGEX.is_match(&line) {
-            let captures = PACKAGE_MESSAGE_REGEX.captures(&line).unwrap();
-            if let Some(last_line) = new_lines.last_mut() {
-                last_line.push(' ');
-                last_line.push_str(captures.name("msg").unwrap().as_str());
+        if let Some(captures) = PACKAGE_MESSAGE_REGEX.captures(&line) {
+            if !prepared.segments.is_empty() {
+                let message = captures.name("msg").unwrap();
+                prepared.text.push(' ');
+                prepared.push_segment(line_offset(line) + message.start(), message.as_str());
             }
         } else if line.ends_with("...") {
-            let mut new_line = line[line.len() - 3..].to_owned();
+            let start = line.len() - 3;
+            prepared.push_line(line_offset(line) + start, &line[start..]);
             if let Some(old_line) = old_lines.next() {
-                new_line.push_str(&old_line);
+                prepared.push_segment(line_offset(old_line), old_line);
             }
-            new_lines.push(new_line);
         } else if line.chars().count() == MAX_LINE_LENGTH {
-            let mut new_line = String::new();
-            new_line.push_str(line);
+            prepared.push_line(line_offset(line), line);
             if let Some(old_line) = old_lines.next() {
-                new_line.push_str(old_line);
+                prepared.push_segment(line_offset(old_line), old_line);
             }
-            new_lines.push(new_line);
         } else {
-            new_lines.push(line.to_owned());
+            prepared.push_line(line_offset(line), line);
         }
     }
-    new_lines.join("\n")
+    prepared
 }
 
 fn create_file_range(parent: Uri, log: &str, result: Match) -> FileRange {
@@ -1280,4 +1425,62 @@ mod tests {
             vec![error1, error2, error3, error4, error5, error6]
         );
     }
+
+    #[test]
+    fn file_stack() {
+        let log = indoc!(
+            r#"
+                (./parent.tex
+                (./child.tex
+                ! Undefined control sequence.
+                l.1 \foo
+                )
+                )
+            "#
+        );
+
+        let actual_matches = parse_build_log_matches(&create_uri("parent.tex"), log);
+
+        assert_eq!(actual_matches.len(), 1);
+        assert_eq!(
+            actual_matches[0].files,
+            vec![create_uri("parent.tex"), create_uri("child.tex")]
+        );
+        assert_eq!(actual_matches[0].error.uri, create_uri("child.tex"));
+        assert_eq!(
+            offset_to_position(log, actual_matches[0].start),
+            Position::new(2, 0)
+        );
+        assert_eq!(
+            offset_to_position(log, actual_matches[0].end),
+            Position::new(3, 3)
+        );
+    }
+
+    #[test]
+    fn entries_original_positions() {
+        let log = format!(
+            "(./parent.tex\n{}\na\n! Undefined control sequence.\nl.1 \\foo\n\
+             LaTeX Warning: Citation `foo' undefined.\n)",
+            "a".repeat(MAX_LINE_LENGTH)
+        );
+
+        let entries = parse_build_log_entries(&create_uri("parent.tex"), &log);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].span,
+            Range::new(Position::new(3, 0), Position::new(4, 3))
+        );
+        assert_eq!(entries[0].raw, "! Undefined control sequence.\nl.1");
+        assert_eq!(
+            entries[0].location,
+            Some(Location::new(
+                create_uri("parent.tex").into(),
+                Range::new(Position::new(0, 0), Position::new(0, 0))
+            ))
+        );
+        assert_eq!(entries[1].span.start, Position::new(5, 0));
+        assert_eq!(entries[1].location, None);
+    }
 }
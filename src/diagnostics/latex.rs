@@ -1,5 +1,8 @@
 use crate::{
-    protocol::{Diagnostic, DiagnosticSeverity, NumberOrString, Range, RangeExt, Uri},
+    protocol::{
+        Diagnostic, DiagnosticSeverity, LatexLintOptions, NumberOrString, Range, RangeExt,
+        ScopedOptions, Uri,
+    },
     workspace::Document,
 };
 use chashmap::CHashMap;
@@ -10,27 +13,54 @@ use futures::{
 use log::trace;
 use once_cell::sync::Lazy;
 use regex::Regex;
-use std::process::Stdio;
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+};
 use tokio::{prelude::*, process::Command};
 
+#[derive(Debug, Clone)]
+struct LintResult {
+    version: Option<i64>,
+    diagnostics: Vec<Diagnostic>,
+}
+
 #[derive(Debug, Default)]
 pub struct LatexDiagnosticsProvider {
-    diagnostics_by_uri: CHashMap<Uri, Vec<Diagnostic>>,
+    results_by_uri: CHashMap<Uri, LintResult>,
     handle: Mutex<Option<AbortHandle>>,
 }
 
 impl LatexDiagnosticsProvider {
     pub fn get(&self, document: &Document) -> Vec<Diagnostic> {
-        match self.diagnostics_by_uri.get(&document.uri) {
-            Some(diagnostics) => diagnostics.to_owned(),
+        match self.results_by_uri.get(&document.uri) {
+            Some(result) => result.diagnostics.to_owned(),
             None => Vec::new(),
         }
     }
 
-    pub async fn update(&self, uri: &Uri, text: &str) {
-        if uri.scheme() != "file" {
-            return;
-        }
+    pub fn remove(&self, uri: &Uri) {
+        self.results_by_uri.remove(uri);
+    }
+
+    pub async fn update(
+        &self,
+        document: &Document,
+        options: &LatexLintOptions,
+        root_directory: &Path,
+    ) {
+        let directory = match document.uri.to_file_path() {
+            Ok(path) => match path.parent() {
+                Some(directory) => directory.to_owned(),
+                None => return,
+            },
+            Err(()) => return,
+        };
+        let rc_file = match &options.rc_file {
+            Some(rc_file) => Some(root_directory.join(rc_file)),
+            None => find_rc_file(&directory, root_directory),
+        };
+        let args = build_args(options, rc_file.as_deref());
 
         let mut handle_guard = self.handle.lock().await;
         if let Some(handle) = &*handle_guard {
@@ -43,8 +73,23 @@ impl LatexDiagnosticsProvider {
 
         let future = Abortable::new(
             async move {
-                self.diagnostics_by_uri
-                    .insert(uri.clone(), lint(text.into()).await.unwrap_or_default());
+                let result = LintResult {
+                    version: document.version,
+                    diagnostics: lint(
+                        document.text.clone(),
+                        &options.executable(),
+                        &args,
+                        &directory,
+                    )
+                    .await
+                    .unwrap_or_default(),
+                };
+
+                self.results_by_uri
+                    .alter(document.uri.clone(), |old_result| match old_result {
+                        Some(old_result) if old_result.version > result.version => Some(old_result),
+                        _ => Some(result),
+                    });
             },
             registration,
         );
@@ -55,12 +100,65 @@ impl LatexDiagnosticsProvider {
     }
 }
 
+/// Looks up the lint options and the root directory in the folder of the given document.
+///
+/// The root directory is `latex.rootDirectory` relative to the folder if set
+/// and the folder itself otherwise.
+pub fn lint_options(options: &ScopedOptions, uri: &Uri) -> (LatexLintOptions, PathBuf) {
+    let (options, current_dir) = options.get(uri);
+    let latex = options.latex.as_ref();
+    let root_directory = latex
+        .and_then(|opts| opts.root_directory.as_ref())
+        .map_or_else(|| current_dir.to_owned(), |dir| current_dir.join(dir));
+    let lint = latex.and_then(|opts| opts.lint.clone()).unwrap_or_default();
+    (lint, root_directory)
+}
+
 pub static LINE_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new("(\\d+):(\\d+):(\\d+):(\\w+):(\\w+):(.*)").unwrap());
 
-async fn lint(text: String) -> io::Result<Vec<Diagnostic>> {
-    let mut process: tokio::process::Child = Command::new("chktex")
-        .args(&["-I0", "-f%l:%c:%d:%k:%n:%m\n"])
+const RC_FILE_NAMES: &[&str] = &[".chktexrc", "chktexrc"];
+
+fn find_rc_file(directory: &Path, root_directory: &Path) -> Option<PathBuf> {
+    for ancestor in directory.ancestors() {
+        for name in RC_FILE_NAMES {
+            let path = ancestor.join(name);
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+
+        if !ancestor.starts_with(root_directory) || ancestor == root_directory {
+            break;
+        }
+    }
+    None
+}
+
+fn build_args(options: &LatexLintOptions, rc_file: Option<&Path>) -> Vec<String> {
+    let mut args = vec!["-I0".to_owned(), "-f%l:%c:%d:%k:%n:%m\n".to_owned()];
+    if let Some(rc_file) = rc_file {
+        args.push("-l".to_owned());
+        args.push(rc_file.to_string_lossy().into_owned());
+    }
+
+    for warning in options.disabled_warnings() {
+        args.push(format!("-n{}", warning));
+    }
+
+    args.extend(options.args());
+    args
+}
+
+async fn lint(
+    text: String,
+    executable: &str,
+    args: &[String],
+    directory: &Path,
+) -> io::Result<Vec<Diagnostic>> {
+    let mut process: tokio::process::Child = Command::new(executable)
+        .args(args)
+        .current_dir(directory)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::null())
@@ -111,3 +209,95 @@ async fn lint(text: String) -> io::Result<Vec<Diagnostic>> {
     }
     Ok(diagnostics)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{FolderOptions, LatexOptions, Options};
+    use std::{env, fs, sync::Arc};
+    use tempfile::tempdir;
+
+    #[test]
+    fn lint_options_folder() {
+        let cwd = env::current_dir().unwrap();
+        let folder = |name: &str, root_directory: Option<&str>, warning: u32| FolderOptions {
+            uri: Uri::from_file_path(cwd.join(name)).unwrap(),
+            path: cwd.join(name),
+            options: Some(Options {
+                latex: Some(LatexOptions {
+                    root_directory: root_directory.map(PathBuf::from),
+                    lint: Some(LatexLintOptions {
+                        disabled_warnings: Some(vec![warning]),
+                        ..LatexLintOptions::default()
+                    }),
+                    ..LatexOptions::default()
+                }),
+                ..Options::default()
+            }),
+        };
+        let options = ScopedOptions {
+            default: Options::default(),
+            current_dir: Arc::new(cwd.clone()),
+            folders: vec![folder("foo", None, 1), folder("bar", Some("baz"), 2)],
+        };
+
+        let uri = Uri::from_file_path(cwd.join("foo").join("qux.tex")).unwrap();
+        let (lint, root_directory) = lint_options(&options, &uri);
+        assert_eq!(lint.disabled_warnings, Some(vec![1]));
+        assert_eq!(root_directory, cwd.join("foo"));
+
+        let uri = Uri::from_file_path(cwd.join("bar").join("qux.tex")).unwrap();
+        let (lint, root_directory) = lint_options(&options, &uri);
+        assert_eq!(lint.disabled_warnings, Some(vec![2]));
+        assert_eq!(root_directory, cwd.join("bar").join("baz"));
+
+        let uri = Uri::from_file_path(cwd.join("qux.tex")).unwrap();
+        let (lint, root_directory) = lint_options(&options, &uri);
+        assert_eq!(lint, LatexLintOptions::default());
+        assert_eq!(root_directory, cwd);
+    }
+
+    #[test]
+    fn find_rc_file_parent() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        let directory = root.join("foo").join("bar");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(root.join(".chktexrc"), "").unwrap();
+
+        assert_eq!(find_rc_file(&directory, root), Some(root.join(".chktexrc")));
+    }
+
+    #[test]
+    fn find_rc_file_outside_root() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("foo");
+        let directory = root.join("bar");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(dir.path().join(".chktexrc"), "").unwrap();
+
+        assert_eq!(find_rc_file(&directory, &root), None);
+    }
+
+    #[test]
+    fn build_args_options() {
+        let options = LatexLintOptions {
+            args: Some(vec!["-v0".into()]),
+            disabled_warnings: Some(vec![1, 36]),
+            ..LatexLintOptions::default()
+        };
+
+        assert_eq!(
+            build_args(&options, Some(Path::new("chktexrc"))),
+            vec![
+                "-I0",
+                "-f%l:%c:%d:%k:%n:%m\n",
+                "-l",
+                "chktexrc",
+                "-n1",
+                "-n36",
+                "-v0"
+            ]
+        );
+    }
+}
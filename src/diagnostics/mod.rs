@@ -4,17 +4,26 @@ mod latex;
 
 pub use self::{
     bibtex::{BibtexDiagnosticsProvider, BibtexError, BibtexErrorCode},
-    build::BuildDiagnosticsProvider,
-    latex::LatexDiagnosticsProvider,
+    build::{build_log, BuildDiagnosticsProvider},
+    latex::{lint_options, LatexDiagnosticsProvider},
 };
 
-use crate::{protocol::Diagnostic, workspace::Document};
+use crate::{
+    protocol::{Diagnostic, Uri},
+    workspace::Document,
+};
+use chashmap::CHashMap;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
 
 #[derive(Debug, Default)]
 pub struct DiagnosticsManager {
     pub bibtex: BibtexDiagnosticsProvider,
     pub latex: LatexDiagnosticsProvider,
     pub build: BuildDiagnosticsProvider,
+    hashes_by_uri: CHashMap<Uri, u64>,
 }
 
 impl DiagnosticsManager {
@@ -25,4 +34,48 @@ impl DiagnosticsManager {
         diagnostics.append(&mut self.build.get(doc).await);
         diagnostics
     }
+
+    pub async fn remove(&self, uri: &Uri) {
+        self.latex.remove(uri);
+        self.build.remove(uri).await;
+        self.hashes_by_uri.remove(uri);
+    }
+
+    pub fn has_changed(&self, uri: &Uri, diagnostics: &[Diagnostic]) -> bool {
+        let hash = Self::hash(diagnostics);
+        let old_hash = self
+            .hashes_by_uri
+            .insert(uri.clone(), hash)
+            .unwrap_or_else(|| Self::hash(&[]));
+        old_hash != hash
+    }
+
+    pub fn hash(diagnostics: &[Diagnostic]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(diagnostics)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{Position, Range};
+
+    #[test]
+    fn has_changed() {
+        let manager = DiagnosticsManager::default();
+        let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
+        let diagnostic = Diagnostic::new_simple(
+            Range::new(Position::new(0, 0), Position::new(0, 1)),
+            "foo".into(),
+        );
+
+        assert!(!manager.has_changed(&uri, &[]));
+        assert!(manager.has_changed(&uri, &[diagnostic.clone()]));
+        assert!(!manager.has_changed(&uri, &[diagnostic]));
+        assert!(manager.has_changed(&uri, &[]));
+    }
 }
@@ -0,0 +1,235 @@
+use crate::{
+    protocol::{Options, Uri},
+    syntax::latex,
+    tex::Resolver,
+};
+use fnv::FnvHasher;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    hash::Hasher,
+    path::{Path, PathBuf},
+    process,
+    time::SystemTime,
+};
+use tokio::fs;
+
+const CACHE_VERSION: u32 = 3;
+
+const MAX_ENTRIES: usize = 4096;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub struct CacheKey {
+    modified: SystemTime,
+    text_hash: u64,
+    context_hash: u64,
+}
+
+impl CacheKey {
+    pub fn new(
+        modified: SystemTime,
+        text: &str,
+        resolver: &Resolver,
+        options: &Options,
+        current_dir: &Path,
+    ) -> Self {
+        let options = serde_json::to_string(options).unwrap_or_default();
+        let current_dir = current_dir.to_string_lossy();
+        Self {
+            modified,
+            text_hash: stable_hash(&[text.as_bytes()]),
+            context_hash: stable_hash(&[
+                resolver.distro_version.as_bytes(),
+                options.as_bytes(),
+                current_dir.as_bytes(),
+            ]),
+        }
+    }
+}
+
+/// Hashes the given bytes with a function that does not change between
+/// compiler versions or processes.
+fn stable_hash(parts: &[&[u8]]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    for part in parts {
+        hasher.write(part);
+        hasher.write_u8(0xff);
+    }
+    hasher.finish()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntry {
+    version: u32,
+    uri: Uri,
+    key: CacheKey,
+    table: latex::SymbolTable,
+}
+
+/// Persists the analysis of LaTeX documents between sessions.
+///
+/// Every project gets its own directory with one file per document.
+/// Entries are read when a document is loaded and written as soon as it has been parsed.
+#[derive(Debug, Default)]
+pub struct DocumentCache {
+    dir: Option<PathBuf>,
+}
+
+impl DocumentCache {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    pub async fn get(&self, uri: &Uri, key: CacheKey) -> Option<latex::SymbolTable> {
+        let path = self.entry_path(uri)?;
+        let bytes = fs::read(&path).await.ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&bytes).ok()?;
+        if entry.version == CACHE_VERSION && entry.uri == *uri && entry.key == key {
+            Some(entry.table)
+        } else {
+            None
+        }
+    }
+
+    pub async fn insert(&self, uri: Uri, key: CacheKey, table: &latex::SymbolTable) {
+        let path = match self.entry_path(&uri) {
+            Some(path) => path,
+            None => return,
+        };
+
+        let entry = CacheEntry {
+            version: CACHE_VERSION,
+            uri,
+            key,
+            table: table.clone(),
+        };
+
+        let result = async {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).await?;
+            }
+            // Another instance may read the same entry in the meantime.
+            let temp_path = path.with_extension(format!("{}.tmp", process::id()));
+            fs::write(&temp_path, serde_json::to_vec(&entry)?).await?;
+            fs::rename(&temp_path, &path).await
+        };
+
+        if let Err(why) = result.await {
+            warn!("Unable to write document cache: {}", why);
+        }
+    }
+
+    /// Removes the least recently written entries if the cache has grown too large.
+    pub async fn prune(&self) {
+        let dir = match &self.dir {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        let mut entries = Vec::new();
+        if let Ok(mut dir_entries) = fs::read_dir(dir).await {
+            while let Ok(Some(entry)) = dir_entries.next_entry().await {
+                let modified = entry
+                    .metadata()
+                    .await
+                    .and_then(|meta| meta.modified())
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                entries.push((modified, entry.path()));
+            }
+        }
+
+        if entries.len() > MAX_ENTRIES {
+            entries.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+            for (_, path) in &entries[MAX_ENTRIES..] {
+                let _ = fs::remove_file(path).await;
+            }
+        }
+    }
+
+    fn entry_path(&self, uri: &Uri) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        let hash = stable_hash(&[uri.as_str().as_bytes()]);
+        Some(dir.join(format!("{:016x}.json", hash)))
+    }
+}
+
+pub fn cache_path(cache_dir: &Path, current_dir: &Path) -> PathBuf {
+    let hash = stable_hash(&[current_dir.to_string_lossy().as_bytes()]);
+    cache_dir.join("documents").join(format!("{:016x}", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use tempfile::tempdir;
+
+    fn create_key(modified: SystemTime, text: &str) -> CacheKey {
+        CacheKey::new(
+            modified,
+            text,
+            &Resolver::default(),
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        )
+    }
+
+    fn create_table(uri: &Uri, text: &str) -> latex::SymbolTable {
+        latex::open(latex::OpenParams {
+            text,
+            uri,
+            resolver: &Resolver::default(),
+            options: &Options::default(),
+            current_dir: &env::current_dir().unwrap(),
+        })
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let dir = tempdir().unwrap();
+        let file = dir.path().join("foo.tex");
+        let text = "\\section{Foo}\\label{sec:foo}";
+        std::fs::write(&file, text).unwrap();
+        let uri = Uri::from_file_path(&file).unwrap();
+        let path = cache_path(&dir.path().join("cache"), dir.path());
+        let modified = SystemTime::now();
+        let key = create_key(modified, text);
+
+        DocumentCache::new(Some(path.clone()))
+            .insert(uri.clone(), key, &create_table(&uri, text))
+            .await;
+
+        let cache = DocumentCache::new(Some(path));
+        let table = cache.get(&uri, key).await.unwrap();
+        assert_eq!(table.labels.len(), 1);
+        assert_eq!(table.labels[0].names(&table)[0].text(), "sec:foo");
+        assert_eq!(table.tree, create_table(&uri, text).tree);
+        let stale_key = create_key(modified, "\\section{Bar}");
+        assert!(cache.get(&uri, stale_key).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn disabled() {
+        let uri = Uri::parse("file:///foo.tex").unwrap();
+        let key = create_key(SystemTime::now(), "");
+        let cache = DocumentCache::new(None);
+        cache
+            .insert(uri.clone(), key, &create_table(&uri, ""))
+            .await;
+        assert!(cache.get(&uri, key).await.is_none());
+    }
+
+    #[test]
+    fn cache_path_per_project() {
+        let cache_dir = Path::new("cache");
+        assert_ne!(
+            cache_path(cache_dir, Path::new("foo")),
+            cache_path(cache_dir, Path::new("bar"))
+        );
+    }
+
+    #[test]
+    fn stable_hash_known_value() {
+        assert_eq!(stable_hash(&[b"foo"]), 0xdd12_8179_0c25_d618);
+    }
+}
@@ -1,47 +1,59 @@
 use crate::{
+    component_index::{LocalComponents, COMPONENT_INDEX},
     components::{Component, COMPONENT_DATABASE},
     protocol::*,
+    syntax::LatexLabelReferenceSource,
     tex::{Distribution, Language, UnknownDistribution},
     workspace::{Document, DocumentContent, DocumentParams, Snapshot},
 };
 use async_trait::async_trait;
 use itertools::Itertools;
-use std::{
-    env,
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{collections::BTreeMap, env, path::PathBuf, sync::Arc};
+
+static MAGIC_PROGRAM_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?im)^\s*%\s*!\s*TEX\s+(?:TS-)?program\s*=\s*(?P<program>[A-Za-z-]+)").unwrap()
+});
+
+const UNICODE_ENGINES: &[&str] = &["xelatex", "lualatex", "xetex", "luatex", "tectonic"];
+
+const UNICODE_PACKAGES: &[&str] = &["fontspec.sty", "unicode-math.sty"];
+
+const UNICODE_BUILD_ARGS: &[&str] = &["-xelatex", "-lualatex", "-pdfxe", "-pdflua"];
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct DocumentView {
     pub snapshot: Arc<Snapshot>,
     pub current: Arc<Document>,
     pub related: Vec<Arc<Document>>,
+    pub local_components: LocalComponents,
 }
 
 impl DocumentView {
-    pub fn analyze(
-        snapshot: Arc<Snapshot>,
-        current: Arc<Document>,
-        options: &Options,
-        current_dir: &Path,
-    ) -> Self {
-        let related = snapshot.relations(&current.uri, options, current_dir);
+    pub fn analyze(snapshot: Arc<Snapshot>, current: Arc<Document>) -> Self {
+        let related = snapshot.relations(&current.uri);
         Self {
             snapshot,
             current,
             related,
+            local_components: COMPONENT_INDEX.components(),
         }
     }
 
-    pub fn components(&self) -> Vec<&'static Component> {
+    pub fn components(&self) -> Vec<&Component> {
+        let find = |file: &str| {
+            let local = self.local_components.get(file).map(AsRef::as_ref);
+            COMPONENT_DATABASE.find(file).into_iter().chain(local)
+        };
+
         let mut start_components = vec![COMPONENT_DATABASE.kernel()];
         for doc in &self.related {
             if let DocumentContent::Latex(table) = &doc.content {
                 table
                     .components
                     .iter()
-                    .flat_map(|file| COMPONENT_DATABASE.find(file))
+                    .flat_map(|file| find(file.as_str()))
                     .for_each(|component| start_components.push(component))
             }
         }
@@ -52,13 +64,13 @@ impl DocumentView {
             component
                 .references
                 .iter()
-                .flat_map(|file| COMPONENT_DATABASE.find(&file))
+                .flat_map(|file| find(file.as_str()))
                 .for_each(|component| all_components.push(component))
         }
 
         all_components
             .into_iter()
-            .unique_by(|component| &component.file_names)
+            .unique_by(|component| *component as *const Component)
             .collect()
     }
 }
@@ -85,6 +97,43 @@ impl<P> FeatureRequest<P> {
     pub fn related(&self) -> &[Arc<Document>] {
         &self.view.related
     }
+
+    pub fn is_unicode_input_allowed(&self) -> bool {
+        let latex_docs = self
+            .related()
+            .iter()
+            .filter(|doc| doc.content.as_latex().is_some());
+
+        for doc in latex_docs.clone() {
+            if let Some(captures) = MAGIC_PROGRAM_REGEX.captures(&doc.text) {
+                let program = captures["program"].to_lowercase();
+                return UNICODE_ENGINES.contains(&program.as_str());
+            }
+        }
+
+        let has_unicode_package = latex_docs
+            .filter_map(|doc| doc.content.as_latex())
+            .flat_map(|table| table.components.iter())
+            .any(|file| UNICODE_PACKAGES.contains(&file.as_str()));
+        if has_unicode_package {
+            return true;
+        }
+
+        let build = self
+            .options
+            .latex
+            .as_ref()
+            .and_then(|opts| opts.build.clone())
+            .unwrap_or_default();
+        let executable = build.executable().to_lowercase();
+        UNICODE_ENGINES
+            .iter()
+            .any(|engine| executable.contains(engine))
+            || build
+                .args()
+                .iter()
+                .any(|arg| UNICODE_BUILD_ARGS.contains(&arg.as_str()))
+    }
 }
 
 #[async_trait]
@@ -165,12 +214,15 @@ pub struct FeatureTester {
     files: Vec<(String, String)>,
     distro: Arc<dyn Distribution>,
     position: Position,
+    range: Range,
     new_name: String,
     include_declaration: bool,
     client_capabilities: Arc<ClientCapabilities>,
     current_dir: Arc<PathBuf>,
     root_dir: Option<PathBuf>,
     output_dir: Option<PathBuf>,
+    snippets: Vec<LatexSnippet>,
+    reference_commands: BTreeMap<String, LatexLabelReferenceSource>,
 }
 
 impl Default for FeatureTester {
@@ -186,12 +238,15 @@ impl FeatureTester {
             files: Vec::new(),
             distro: Arc::new(UnknownDistribution::default()),
             position: Position::default(),
+            range: Range::default(),
             new_name: String::new(),
             include_declaration: false,
             client_capabilities: Arc::default(),
             current_dir: Arc::new(env::temp_dir()),
             root_dir: None,
             output_dir: None,
+            snippets: Vec::new(),
+            reference_commands: BTreeMap::new(),
         }
     }
 
@@ -214,6 +269,17 @@ impl FeatureTester {
         self
     }
 
+    pub fn range(
+        &mut self,
+        start_line: u64,
+        start_character: u64,
+        end_line: u64,
+        end_character: u64,
+    ) -> &mut Self {
+        self.range = Range::new_simple(start_line, start_character, end_line, end_character);
+        self
+    }
+
     pub fn new_name<S: Into<String>>(&mut self, value: S) -> &mut Self {
         self.new_name = value.into();
         self
@@ -234,6 +300,28 @@ impl FeatureTester {
         self
     }
 
+    pub fn snippet<S, T>(&mut self, prefix: S, body: T) -> &mut Self
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        self.snippets.push(LatexSnippet {
+            prefix: prefix.into(),
+            body: body.into(),
+            description: None,
+        });
+        self
+    }
+
+    pub fn reference_command<S: Into<String>>(
+        &mut self,
+        name: S,
+        source: LatexLabelReferenceSource,
+    ) -> &mut Self {
+        self.reference_commands.insert(name.into(), source);
+        self
+    }
+
     pub fn uri(name: &str) -> Uri {
         let path = env::temp_dir().join(name);
         Uri::from_file_path(path).unwrap()
@@ -252,6 +340,8 @@ impl FeatureTester {
                     ..LatexBuildOptions::default()
                 }),
                 root_directory: self.root_dir.clone(),
+                snippets: Some(self.snippets.clone()),
+                reference_commands: Some(self.reference_commands.clone()),
                 ..LatexOptions::default()
             }),
             ..Options::default()
@@ -278,10 +368,10 @@ impl FeatureTester {
                 options: &options,
                 current_dir: &self.current_dir,
             });
-            snapshot.push(doc);
+            snapshot.insert(Arc::new(doc), &options, &self.current_dir);
         }
         let current = snapshot.find(&Self::uri(&self.main)).unwrap();
-        DocumentView::analyze(Arc::new(snapshot), current, &options, &self.current_dir)
+        DocumentView::analyze(Arc::new(snapshot), current)
     }
 
     async fn request<P>(&self, params: P) -> FeatureRequest<P> {
@@ -326,6 +416,21 @@ impl FeatureTester {
         self.request(params).await
     }
 
+    pub async fn test_code_action<F, O>(&self, provider: F) -> O
+    where
+        F: FeatureProvider<Params = CodeActionParams, Output = O>,
+    {
+        let params = CodeActionParams {
+            text_document: self.identifier(),
+            range: self.range,
+            context: CodeActionContext::default(),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        let req = self.request(params).await;
+        provider.execute(&req).await
+    }
+
     pub async fn test_folding<F, O>(&self, provider: F) -> O
     where
         F: FeatureProvider<Params = FoldingRangeParams, Output = O>,
@@ -15,7 +15,7 @@ pub async fn search<'a>(
 ) -> Option<ForwardSearchResult> {
     let pdf_path = snapshot
         .resolve_aux_targets(
-            &snapshot.parent_subfile(tex_uri, options, current_dir)?.uri,
+            &snapshot.parent_subfile(tex_uri)?.uri,
             options,
             current_dir,
             "pdf",
@@ -0,0 +1,114 @@
+use crate::{
+    feature::{FeatureProvider, FeatureRequest},
+    protocol::{Hover, HoverContents, MarkupContent, MarkupKind, TextDocumentPositionParams},
+    syntax::SyntaxNode,
+};
+use async_trait::async_trait;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LatexEnvironmentHoverProvider;
+
+#[async_trait]
+impl FeatureProvider for LatexEnvironmentHoverProvider {
+    type Params = TextDocumentPositionParams;
+    type Output = Option<Hover>;
+
+    async fn execute<'a>(&'a self, req: &'a FeatureRequest<Self::Params>) -> Self::Output {
+        let table = req.current().content.as_latex()?;
+        let name = table.find_environment_name(req.params.position)?;
+        let definition = req
+            .related()
+            .iter()
+            .filter_map(|doc| doc.content.as_latex())
+            .find_map(|table| {
+                let env_defs = table
+                    .environment_definitions
+                    .iter()
+                    .map(|def| (def.parent, def.name(&table)));
+                let thm_defs = table
+                    .theorem_definitions
+                    .iter()
+                    .map(|def| (def.parent, def.name(&table)));
+
+                env_defs
+                    .chain(thm_defs)
+                    .find(|(_, def_name)| def_name.text() == name.text())
+                    .map(|(parent, _)| table.print(parent))
+            })?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```latex\n{}\n```", definition),
+            }),
+            range: Some(name.range()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        feature::FeatureTester,
+        protocol::{Range, RangeExt},
+    };
+    use indoc::indoc;
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let actual_hover = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_position(LatexEnvironmentHoverProvider)
+            .await;
+
+        assert_eq!(actual_hover, None);
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let actual_hover = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .position(0, 0)
+            .test_position(LatexEnvironmentHoverProvider)
+            .await;
+
+        assert_eq!(actual_hover, None);
+    }
+
+    #[tokio::test]
+    async fn environment_definition() {
+        let actual_hover = FeatureTester::new()
+            .file(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \newenvironment{note}{\itshape}{}
+                        \begin{note}
+                    "#
+                ),
+            )
+            .main("main.tex")
+            .position(1, 9)
+            .test_position(LatexEnvironmentHoverProvider)
+            .await
+            .unwrap();
+
+        assert_eq!(actual_hover.range.unwrap(), Range::new_simple(1, 7, 1, 11));
+    }
+
+    #[tokio::test]
+    async fn unknown_environment() {
+        let actual_hover = FeatureTester::new()
+            .file("main.tex", r#"\begin{itemize}"#)
+            .main("main.tex")
+            .position(0, 9)
+            .test_position(LatexEnvironmentHoverProvider)
+            .await;
+
+        assert_eq!(actual_hover, None);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    completion::KeyValueContext,
+    feature::{FeatureProvider, FeatureRequest},
+    protocol::{
+        Hover, HoverContents, MarkupContent, MarkupKind, Position, Range,
+        TextDocumentPositionParams,
+    },
+    syntax::SyntaxNode,
+};
+use async_trait::async_trait;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct LatexKeyValueHoverProvider;
+
+#[async_trait]
+impl FeatureProvider for LatexKeyValueHoverProvider {
+    type Params = TextDocumentPositionParams;
+    type Output = Option<Hover>;
+
+    async fn execute<'a>(&'a self, req: &'a FeatureRequest<Self::Params>) -> Self::Output {
+        let table = req.current().content.as_latex()?;
+        let pos = req.params.position;
+        let ctx = KeyValueContext::find(table, pos)?;
+        let word = ctx.word?;
+        let start = word.start();
+        let index = (pos.character - start.character) as usize;
+        let text = word.text();
+
+        let (name, range) = if ctx.target.concatenated {
+            let (offset, c) = text.char_indices().nth(index)?;
+            let start = Position::new(start.line, start.character + index as u64);
+            let end = Position::new(start.line, start.character + 1);
+            (&text[offset..offset + c.len_utf8()], Range::new(start, end))
+        } else {
+            let name = text.split('=').next().unwrap();
+            let length = name.chars().count();
+            if index > length {
+                return None;
+            }
+            let end = Position::new(start.line, start.character + length as u64);
+            (name, Range::new(start, end))
+        };
+
+        let key = ctx.target.find(name)?;
+        let mut value = format!("**{}** ({})", key.name, key.detail());
+        if let Some(documentation) = &key.documentation {
+            value.push_str("\n\n");
+            value.push_str(documentation);
+        }
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: Some(range),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{feature::FeatureTester, protocol::RangeExt};
+
+    #[tokio::test]
+    async fn empty_latex_document() {
+        let actual_hover = FeatureTester::new()
+            .file("main.tex", "")
+            .main("main.tex")
+            .position(0, 0)
+            .test_position(LatexKeyValueHoverProvider)
+            .await;
+
+        assert_eq!(actual_hover, None);
+    }
+
+    #[tokio::test]
+    async fn empty_bibtex_document() {
+        let actual_hover = FeatureTester::new()
+            .file("main.bib", "")
+            .main("main.bib")
+            .position(0, 0)
+            .test_position(LatexKeyValueHoverProvider)
+            .await;
+
+        assert_eq!(actual_hover, None);
+    }
+
+    #[tokio::test]
+    async fn command_key() {
+        let actual_hover = FeatureTester::new()
+            .file("main.tex", r#"\includegraphics[width=\textwidth]{foo}"#)
+            .main("main.tex")
+            .position(0, 19)
+            .test_position(LatexKeyValueHoverProvider)
+            .await
+            .unwrap();
+
+        assert_eq!(actual_hover.range.unwrap(), Range::new_simple(0, 17, 0, 22));
+    }
+
+    #[tokio::test]
+    async fn environment_placement() {
+        let actual_hover = FeatureTester::new()
+            .file("main.tex", r#"\begin{figure}[htb]"#)
+            .main("main.tex")
+            .position(0, 16)
+            .test_position(LatexKeyValueHoverProvider)
+            .await
+            .unwrap();
+
+        assert_eq!(actual_hover.range.unwrap(), Range::new_simple(0, 16, 0, 17));
+    }
+
+    #[tokio::test]
+    async fn unknown_key() {
+        let actual_hover = FeatureTester::new()
+            .file("main.tex", r#"\usepackage[foo]{geometry}"#)
+            .main("main.tex")
+            .position(0, 13)
+            .test_position(LatexKeyValueHoverProvider)
+            .await;
+
+        assert_eq!(actual_hover, None);
+    }
+}
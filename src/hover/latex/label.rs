@@ -22,8 +22,8 @@ impl FeatureProvider for LatexLabelHoverProvider {
         let (doc, def) = Self::find_definition(&req.view, reference)?;
 
         let snapshot = Arc::clone(&req.view.snapshot);
-        let view = DocumentView::analyze(snapshot, doc, &req.options, &req.current_dir);
-        let outline = Outline::analyze(&view, &req.options, &req.current_dir);
+        let view = DocumentView::analyze(snapshot, doc);
+        let outline = Outline::analyze(&view);
         let outline_ctx = OutlineContext::parse(&view, &outline, def)?;
         let markup = outline_ctx.documentation();
         Some(Hover {
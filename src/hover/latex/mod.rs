@@ -2,5 +2,7 @@
 pub mod citation;
 
 pub mod component;
+pub mod environment;
+pub mod key_value;
 pub mod label;
 pub mod preview;
@@ -10,7 +10,8 @@ use self::{
         string_reference::BibtexStringReferenceHoverProvider,
     },
     latex::{
-        component::LatexComponentHoverProvider, label::LatexLabelHoverProvider,
+        component::LatexComponentHoverProvider, environment::LatexEnvironmentHoverProvider,
+        key_value::LatexKeyValueHoverProvider, label::LatexLabelHoverProvider,
         preview::LatexPreviewHoverProvider,
     },
 };
@@ -34,6 +35,8 @@ impl HoverProvider {
                 #[cfg(feature = "citation")]
                 Box::new(LatexCitationHoverProvider),
                 Box::new(LatexComponentHoverProvider),
+                Box::new(LatexEnvironmentHoverProvider),
+                Box::new(LatexKeyValueHoverProvider),
                 Box::new(LatexLabelHoverProvider),
                 Box::new(LatexPreviewHoverProvider),
             ]),
@@ -13,10 +13,14 @@ cfg_if::cfg_if! {
     }
 }
 
+pub mod clean;
+pub mod code_action;
 pub mod completion;
+pub mod component_index;
 pub mod components;
 pub mod definition;
 pub mod diagnostics;
+pub mod document_cache;
 pub mod feature;
 pub mod folding;
 pub mod forward_search;
@@ -24,10 +28,12 @@ pub mod highlight;
 pub mod hover;
 pub mod link;
 pub mod outline;
+pub mod output;
 pub mod protocol;
 pub mod reference;
 pub mod rename;
 pub mod symbol;
+pub mod synctex;
 pub mod syntax;
 pub mod tex;
 pub mod workspace;
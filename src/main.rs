@@ -40,12 +40,9 @@ async fn main() -> Result<(), Box<dyn error::Error>> {
         Distribution::detect().await,
         Arc::clone(&client),
         Arc::new(env::current_dir().expect("failed to get working directory")),
+        dirs::cache_dir().map(|dir| dir.join("texlab")),
     ));
-    let mut handler = MessageHandler {
-        server,
-        client,
-        output: stdout_tx,
-    };
+    let mut handler = MessageHandler::new(server, client, stdout_tx);
 
     tokio::spawn(async move {
         let mut stdout = FramedWrite::new(tokio::io::stdout(), LspCodec);
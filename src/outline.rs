@@ -1,10 +1,10 @@
 use crate::{
     feature::DocumentView,
-    protocol::{MarkupContent, MarkupKind, Options, Position, Range, RangeExt, Uri},
+    protocol::{MarkupContent, MarkupKind, Position, Range, RangeExt, Uri},
     syntax::{latex, SyntaxNode},
     workspace::{Document, DocumentContent},
 };
-use std::{borrow::Cow, collections::HashSet, path::Path};
+use std::{borrow::Cow, collections::HashSet};
 use titlecase::titlecase;
 use OutlineContextItem::*;
 
@@ -31,11 +31,11 @@ impl<'a> Outline<'a> {
             .map(|sec| sec.item)
     }
 
-    pub fn analyze(view: &'a DocumentView, options: &Options, current_dir: &Path) -> Self {
+    pub fn analyze(view: &'a DocumentView) -> Self {
         let mut finder = OutlineSectionFinder::default();
         let doc = view
             .snapshot
-            .parent(&view.current.uri, options, current_dir)
+            .parent(&view.current.uri)
             .map(|parent| {
                 view.related
                     .iter()
@@ -0,0 +1,267 @@
+use crate::protocol::Options;
+use itertools::Itertools;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+static LATEXMKRC_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new("(?m)^\\s*\\$(?P<name>out_dir|aux_dir|jobname)\\s*=\\s*['\"](?P<value>[^'\"]*)['\"]")
+        .unwrap()
+});
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct LatexmkConfig {
+    pub out_dir: Option<PathBuf>,
+    pub aux_dir: Option<PathBuf>,
+    pub jobname: Option<String>,
+}
+
+impl LatexmkConfig {
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        for captures in LATEXMKRC_REGEX.captures_iter(text) {
+            let value = captures["value"].to_owned();
+            match &captures["name"] {
+                "out_dir" => config.out_dir = Some(value.into()),
+                "aux_dir" => config.aux_dir = Some(value.into()),
+                _ => config.jobname = Some(value),
+            }
+        }
+        config
+    }
+
+    pub fn load(directory: &Path) -> Self {
+        ["latexmkrc", ".latexmkrc"]
+            .iter()
+            .find_map(|name| fs::read_to_string(directory.join(name)).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn from_args(args: &[String]) -> Self {
+        let mut config = Self::default();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with('-') {
+                continue;
+            }
+
+            let arg = arg.trim_start_matches('-');
+            let (name, value) = match arg.find('=') {
+                Some(index) => (&arg[..index], Some(arg[index + 1..].to_owned())),
+                None => (arg, None),
+            };
+
+            match name {
+                "outdir" | "out-dir" | "output-directory" => {
+                    config.out_dir = value.or_else(|| args.next().cloned()).map(Into::into);
+                }
+                "auxdir" | "aux-directory" => {
+                    config.aux_dir = value.or_else(|| args.next().cloned()).map(Into::into);
+                }
+                "jobname" => {
+                    config.jobname = value.or_else(|| args.next().cloned());
+                }
+                _ => (),
+            }
+        }
+        config
+    }
+
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            out_dir: other.out_dir.or(self.out_dir),
+            aux_dir: other.aux_dir.or(self.aux_dir),
+            jobname: other.jobname.or(self.jobname),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OutputLocations {
+    pub build_dir: PathBuf,
+    pub jobname: String,
+    pub out_dirs: Vec<PathBuf>,
+    pub aux_dirs: Vec<PathBuf>,
+}
+
+impl OutputLocations {
+    pub fn resolve(tex_path: &Path, options: &Options, current_dir: &Path) -> Option<Self> {
+        let tex_dir = tex_path.parent()?;
+        let file_stem = tex_path.file_stem()?.to_str()?;
+        let latex_options = options.latex.clone().unwrap_or_default();
+        let build_options = latex_options.build.clone().unwrap_or_default();
+        let root_dir = latex_options
+            .root_directory
+            .as_ref()
+            .map(|dir| current_dir.join(dir));
+
+        let build_dir = root_dir.clone().unwrap_or_else(|| tex_dir.to_path_buf());
+        let config =
+            LatexmkConfig::load(&build_dir).merge(LatexmkConfig::from_args(&build_options.args()));
+
+        let jobname = config
+            .jobname
+            .map(|jobname| jobname.replace("%A", file_stem))
+            .unwrap_or_else(|| file_stem.to_owned());
+
+        let mut out_dirs = Vec::new();
+        out_dirs.extend(config.out_dir.map(|dir| build_dir.join(dir)));
+        out_dirs.push(tex_dir.to_path_buf());
+        out_dirs.extend(root_dir);
+        out_dirs.extend(
+            build_options
+                .output_directory
+                .as_ref()
+                .map(|dir| current_dir.join(dir)),
+        );
+
+        let mut aux_dirs = Vec::new();
+        aux_dirs.extend(config.aux_dir.map(|dir| build_dir.join(dir)));
+        aux_dirs.extend(out_dirs.iter().cloned());
+
+        Some(Self {
+            build_dir,
+            jobname,
+            out_dirs: out_dirs.into_iter().unique().collect(),
+            aux_dirs: aux_dirs.into_iter().unique().collect(),
+        })
+    }
+
+    pub fn file_name(&self, extension: &str) -> String {
+        format!("{}.{}", self.jobname, extension)
+    }
+
+    pub fn directories(&self, extension: &str) -> &[PathBuf] {
+        if Self::is_final_output(extension) {
+            &self.out_dirs
+        } else {
+            &self.aux_dirs
+        }
+    }
+
+    pub fn paths(&self, extension: &str) -> Vec<PathBuf> {
+        let file_name = self.file_name(extension);
+        self.directories(extension)
+            .iter()
+            .map(|dir| dir.join(&file_name))
+            .collect()
+    }
+
+    fn is_final_output(extension: &str) -> bool {
+        matches!(
+            extension,
+            "pdf" | "dvi" | "ps" | "xdv" | "synctex" | "synctex.gz"
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::{LatexBuildOptions, LatexOptions};
+    use indoc::indoc;
+    use std::env;
+
+    #[test]
+    fn parse_latexmkrc() {
+        let config = LatexmkConfig::parse(indoc!(
+            r#"
+                $pdf_mode = 1;
+                $out_dir = 'build';
+                # $aux_dir = "ignored";
+                $jobname = "%A-draft";
+            "#
+        ));
+
+        assert_eq!(
+            config,
+            LatexmkConfig {
+                out_dir: Some("build".into()),
+                aux_dir: None,
+                jobname: Some("%A-draft".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_args() {
+        let args: Vec<String> = vec![
+            "-pdf".into(),
+            "-outdir=out".into(),
+            "--aux-directory".into(),
+            "aux".into(),
+            "-jobname=thesis".into(),
+            "%f".into(),
+        ];
+
+        assert_eq!(
+            LatexmkConfig::from_args(&args),
+            LatexmkConfig {
+                out_dir: Some("out".into()),
+                aux_dir: Some("aux".into()),
+                jobname: Some("thesis".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn merge_prefers_args() {
+        let rc = LatexmkConfig {
+            out_dir: Some("build".into()),
+            aux_dir: Some("aux".into()),
+            jobname: None,
+        };
+        let args = LatexmkConfig {
+            out_dir: Some("out".into()),
+            ..LatexmkConfig::default()
+        };
+
+        assert_eq!(
+            rc.merge(args),
+            LatexmkConfig {
+                out_dir: Some("out".into()),
+                aux_dir: Some("aux".into()),
+                jobname: None,
+            }
+        );
+    }
+
+    #[test]
+    fn resolve_build_args() {
+        let cwd = env::temp_dir();
+        let options = Options {
+            latex: Some(LatexOptions {
+                build: Some(LatexBuildOptions {
+                    args: Some(vec![
+                        "-auxdir=aux".into(),
+                        "-outdir=out".into(),
+                        "-jobname=thesis".into(),
+                    ]),
+                    ..LatexBuildOptions::default()
+                }),
+                ..LatexOptions::default()
+            }),
+            ..Options::default()
+        };
+
+        let tex_path = cwd.join("src/main.tex");
+        let locations = OutputLocations::resolve(&tex_path, &options, &cwd).unwrap();
+        assert_eq!(locations.build_dir, cwd.join("src"));
+        assert_eq!(
+            locations.paths("aux"),
+            vec![
+                cwd.join("src/aux/thesis.aux"),
+                cwd.join("src/out/thesis.aux"),
+                cwd.join("src/thesis.aux"),
+            ]
+        );
+        assert_eq!(
+            locations.paths("pdf"),
+            vec![cwd.join("src/out/thesis.pdf"), cwd.join("src/thesis.pdf")]
+        );
+    }
+}
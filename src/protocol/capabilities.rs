@@ -1,4 +1,5 @@
 use lsp_types::{ClientCapabilities, MarkupKind};
+use serde::{Deserialize, Serialize};
 
 pub trait ClientCapabilitiesExt {
     fn has_definition_link_support(&self) -> bool;
@@ -12,6 +13,8 @@ pub trait ClientCapabilitiesExt {
     fn has_pull_configuration_support(&self) -> bool;
 
     fn has_push_configuration_support(&self) -> bool;
+
+    fn has_file_watching_support(&self) -> bool;
 }
 
 impl ClientCapabilitiesExt for ClientCapabilities {
@@ -55,6 +58,42 @@ impl ClientCapabilitiesExt for ClientCapabilities {
             .and_then(|cap| cap.dynamic_registration)
             == Some(true)
     }
+
+    fn has_file_watching_support(&self) -> bool {
+        self.workspace
+            .as_ref()
+            .and_then(|cap| cap.did_change_watched_files)
+            .and_then(|cap| cap.dynamic_registration)
+            == Some(true)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticClientCapabilities {
+    pub dynamic_registration: Option<bool>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullDiagnosticsTextDocumentCapabilities {
+    pub diagnostic: Option<DiagnosticClientCapabilities>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullDiagnosticsCapabilities {
+    pub text_document: Option<PullDiagnosticsTextDocumentCapabilities>,
+}
+
+impl PullDiagnosticsCapabilities {
+    pub fn has_pull_diagnostics_support(&self) -> bool {
+        self.text_document
+            .as_ref()
+            .and_then(|cap| cap.diagnostic.as_ref())
+            .and_then(|cap| cap.dynamic_registration)
+            == Some(true)
+    }
 }
 
 #[cfg(test)]
@@ -142,4 +181,39 @@ mod tests {
         let capabilities = ClientCapabilities::default();
         assert!(!capabilities.has_hover_markdown_support());
     }
+
+    #[test]
+    fn has_file_watching_support_true() {
+        let capabilities = ClientCapabilities {
+            workspace: Some(WorkspaceClientCapabilities {
+                did_change_watched_files: Some(GenericCapability {
+                    dynamic_registration: Some(true),
+                }),
+                ..WorkspaceClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        };
+        assert!(capabilities.has_file_watching_support());
+    }
+
+    #[test]
+    fn has_file_watching_support_false() {
+        let capabilities = ClientCapabilities::default();
+        assert!(!capabilities.has_file_watching_support());
+    }
+
+    #[test]
+    fn has_pull_diagnostics_support_true() {
+        let capabilities: PullDiagnosticsCapabilities = serde_json::from_value(
+            serde_json::json!({ "textDocument": { "diagnostic": { "dynamicRegistration": true } } }),
+        )
+        .unwrap();
+        assert!(capabilities.has_pull_diagnostics_support());
+    }
+
+    #[test]
+    fn has_pull_diagnostics_support_false() {
+        let capabilities = PullDiagnosticsCapabilities::default();
+        assert!(!capabilities.has_pull_diagnostics_support());
+    }
 }
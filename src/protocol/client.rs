@@ -16,6 +16,9 @@ pub trait LspClient {
     #[jsonrpc_method("textDocument/publishDiagnostics", kind = "notification")]
     async fn publish_diagnostics(&self, params: PublishDiagnosticsParams);
 
+    #[jsonrpc_method("workspace/diagnostic/refresh", kind = "request")]
+    async fn diagnostic_refresh(&self, params: ()) -> Result<()>;
+
     #[jsonrpc_method("$/progress", kind = "notification")]
     async fn progress(&self, params: ProgressParams);
 
@@ -17,7 +17,10 @@ mod range;
 mod uri;
 
 pub use self::{
-    capabilities::ClientCapabilitiesExt,
+    capabilities::{
+        ClientCapabilitiesExt, DiagnosticClientCapabilities, PullDiagnosticsCapabilities,
+        PullDiagnosticsTextDocumentCapabilities,
+    },
     edit::*,
     options::*,
     range::RangeExt,
@@ -49,6 +52,49 @@ pub struct ForwardSearchResult {
     pub status: ForwardSearchStatus,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PdfRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PdfRect {
+    pub fn union(self, other: Self) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    pub fn distance(&self, x: f64, y: f64) -> f64 {
+        let dx = (self.x - x).max(x - (self.x + self.width)).max(0.0);
+        let dy = (self.y - y).max(y - (self.y + self.height)).max(0.0);
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PdfLocation {
+    pub uri: Url,
+    pub page: u64,
+    pub rect: PdfRect,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InverseSearchParams {
+    pub text_document: TextDocumentIdentifier,
+    pub page: u64,
+    pub x: f64,
+    pub y: f64,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BuildParams {
@@ -69,3 +115,64 @@ pub enum BuildStatus {
 pub struct BuildResult {
     pub status: BuildStatus,
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildLogParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildLogEntry {
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+    pub files: Vec<Url>,
+    pub span: Range,
+    pub raw: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BuildLog {
+    pub uri: Url,
+    pub text: String,
+    pub entries: Vec<BuildLogEntry>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanResult {
+    pub deleted_files: Vec<Url>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiagnosticParams {
+    pub text_document: TextDocumentIdentifier,
+    pub identifier: Option<String>,
+    pub previous_result_id: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum DocumentDiagnosticReport {
+    #[serde(rename_all = "camelCase")]
+    Full {
+        result_id: Option<String>,
+        items: Vec<Diagnostic>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unchanged { result_id: String },
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticRegistrationOptions {
+    pub document_selector: Option<DocumentSelector>,
+    pub identifier: Option<String>,
+    pub inter_file_dependencies: bool,
+    pub workspace_diagnostics: bool,
+}
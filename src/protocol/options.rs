@@ -1,5 +1,11 @@
+use super::Uri;
+use crate::syntax::LatexLabelReferenceSource;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -32,6 +38,10 @@ pub struct LatexForwardSearchOptions {
 pub struct LatexLintOptions {
     pub on_change: Option<bool>,
     pub on_save: Option<bool>,
+    pub executable: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub rc_file: Option<PathBuf>,
+    pub disabled_warnings: Option<Vec<u32>>,
 }
 
 impl LatexLintOptions {
@@ -42,6 +52,24 @@ impl LatexLintOptions {
     pub fn on_save(&self) -> bool {
         self.on_save.unwrap_or(false)
     }
+
+    pub fn executable(&self) -> String {
+        self.executable
+            .as_ref()
+            .map(Clone::clone)
+            .unwrap_or_else(|| "chktex".to_owned())
+    }
+
+    pub fn args(&self) -> Vec<String> {
+        self.args.as_ref().map(Clone::clone).unwrap_or_default()
+    }
+
+    pub fn disabled_warnings(&self) -> Vec<u32> {
+        self.disabled_warnings
+            .as_ref()
+            .map(Clone::clone)
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
@@ -81,6 +109,31 @@ impl LatexBuildOptions {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatexIndexingOptions {
+    pub on_startup: Option<bool>,
+    pub exclude: Option<Vec<String>>,
+}
+
+impl LatexIndexingOptions {
+    pub fn on_startup(&self) -> bool {
+        self.on_startup.unwrap_or(false)
+    }
+
+    pub fn exclude(&self) -> Vec<String> {
+        self.exclude.as_ref().map(Clone::clone).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatexSnippet {
+    pub prefix: String,
+    pub body: String,
+    pub description: Option<String>,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LatexOptions {
@@ -88,6 +141,17 @@ pub struct LatexOptions {
     pub lint: Option<LatexLintOptions>,
     pub build: Option<LatexBuildOptions>,
     pub root_directory: Option<PathBuf>,
+    pub snippets: Option<Vec<LatexSnippet>>,
+    pub reference_commands: Option<BTreeMap<String, LatexLabelReferenceSource>>,
+    pub memory_limit_mb: Option<usize>,
+    pub indexing: Option<LatexIndexingOptions>,
+}
+
+impl LatexOptions {
+    /// The maximum size of the loaded documents of a folder in bytes.
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit_mb.map(|limit| limit << 20)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
@@ -102,3 +166,106 @@ pub struct Options {
     pub latex: Option<LatexOptions>,
     pub bibtex: Option<BibtexOptions>,
 }
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FolderOptions {
+    pub uri: Uri,
+    pub path: PathBuf,
+    pub options: Option<Options>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct ScopedOptions {
+    pub default: Options,
+    pub current_dir: Arc<PathBuf>,
+    pub folders: Vec<FolderOptions>,
+}
+
+impl ScopedOptions {
+    pub fn new(default: Options, current_dir: Arc<PathBuf>) -> Self {
+        Self {
+            default,
+            current_dir,
+            folders: Vec::new(),
+        }
+    }
+
+    pub fn folder(&self, uri: &Uri) -> Option<&FolderOptions> {
+        let path = uri.to_file_path().ok()?;
+        self.folders
+            .iter()
+            .filter(|folder| path.starts_with(&folder.path))
+            .max_by_key(|folder| folder.path.components().count())
+    }
+
+    pub fn folder_options<'a>(&'a self, folder: &'a FolderOptions) -> &'a Options {
+        folder.options.as_ref().unwrap_or(&self.default)
+    }
+
+    pub fn get(&self, uri: &Uri) -> (&Options, &Path) {
+        match self.folder(uri) {
+            Some(folder) => (self.folder_options(folder), folder.path.as_path()),
+            None => (&self.default, self.current_dir.as_path()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn scoped_options_nested_folders() {
+        let cwd = env::current_dir().unwrap();
+        let folder = |path: PathBuf| FolderOptions {
+            uri: Uri::from_file_path(&path).unwrap(),
+            options: Some(Options {
+                latex: Some(LatexOptions {
+                    root_directory: Some(path.clone()),
+                    ..LatexOptions::default()
+                }),
+                ..Options::default()
+            }),
+            path,
+        };
+        let options = ScopedOptions {
+            default: Options::default(),
+            current_dir: Arc::new(cwd.clone()),
+            folders: vec![folder(cwd.join("foo")), folder(cwd.join("foo").join("bar"))],
+        };
+
+        let uri = Uri::from_file_path(cwd.join("foo").join("bar").join("baz.tex")).unwrap();
+        assert_eq!(options.get(&uri).1, cwd.join("foo").join("bar"));
+
+        let uri = Uri::from_file_path(cwd.join("foo").join("baz.tex")).unwrap();
+        assert_eq!(options.get(&uri).1, cwd.join("foo"));
+
+        let uri = Uri::from_file_path(cwd.join("baz.tex")).unwrap();
+        assert_eq!(options.get(&uri), (&Options::default(), cwd.as_path()));
+    }
+
+    #[test]
+    fn scoped_options_inherit_default() {
+        let cwd = env::current_dir().unwrap();
+        let default = Options {
+            latex: Some(LatexOptions {
+                root_directory: Some(cwd.clone()),
+                ..LatexOptions::default()
+            }),
+            ..Options::default()
+        };
+        let options = ScopedOptions {
+            default: default.clone(),
+            current_dir: Arc::new(cwd.clone()),
+            folders: vec![FolderOptions {
+                uri: Uri::from_file_path(cwd.join("foo")).unwrap(),
+                path: cwd.join("foo"),
+                options: None,
+            }],
+        };
+
+        let uri = Uri::from_file_path(cwd.join("foo").join("bar.tex")).unwrap();
+        assert_eq!(options.get(&uri), (&default, cwd.join("foo").as_path()));
+    }
+}
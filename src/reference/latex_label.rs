@@ -60,7 +60,7 @@ impl LatexLabelReferenceProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{feature::FeatureTester, protocol::Range};
+    use crate::{feature::FeatureTester, protocol::Range, syntax::LatexLabelReferenceSource};
     use indoc::indoc;
 
     #[tokio::test]
@@ -90,6 +90,32 @@ mod tests {
         assert_eq!(actual_refs, expected_refs);
     }
 
+    #[tokio::test]
+    async fn definition_user_command() {
+        let actual_refs = FeatureTester::new()
+            .file(
+                "foo.tex",
+                indoc!(
+                    r#"
+                        \label{foo}
+                        \myref{foo}
+                    "#
+                ),
+            )
+            .main("foo.tex")
+            .position(0, 8)
+            .reference_command("myref", LatexLabelReferenceSource::Everything)
+            .test_reference(LatexLabelReferenceProvider)
+            .await;
+
+        let expected_refs = vec![Location::new(
+            FeatureTester::uri("foo.tex").into(),
+            Range::new_simple(1, 7, 1, 10),
+        )];
+
+        assert_eq!(actual_refs, expected_refs);
+    }
+
     #[tokio::test]
     async fn definition_include_declaration() {
         let actual_refs = FeatureTester::new()
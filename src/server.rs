@@ -3,11 +3,15 @@ use crate::citeproc::render_citation;
 
 use crate::{
     build::BuildProvider,
+    clean::{clean, CleanTarget},
+    code_action::CodeActionProvider,
     completion::{CompletionItemData, CompletionProvider, COMPLETION_LIMIT},
+    component_index::COMPONENT_INDEX,
     components::COMPONENT_DATABASE,
     config::ConfigManager,
     definition::DefinitionProvider,
-    diagnostics::DiagnosticsManager,
+    diagnostics::{build_log, lint_options, DiagnosticsManager},
+    document_cache,
     feature::{DocumentView, FeatureProvider, FeatureRequest},
     folding::FoldingProvider,
     forward_search,
@@ -18,18 +22,31 @@ use crate::{
     reference::ReferenceProvider,
     rename::{PrepareRenameProvider, RenameProvider},
     symbol::{document_symbols, workspace_symbols, SymbolProvider},
+    synctex::{self, SyncTexCache},
     syntax::{bibtex, latexindent, CharStream, SyntaxNode},
     tex::{Distribution, DistributionKind, KpsewhichError},
     workspace::{DocumentContent, Workspace},
 };
 use async_trait::async_trait;
 use chashmap::CHashMap;
-use futures::lock::Mutex;
+use futures::lock::{Mutex, MutexGuard};
 use jsonrpc::{server::Result, Middleware};
 use jsonrpc_derive::{jsonrpc_method, jsonrpc_server};
 use log::{debug, error, info, warn};
 use once_cell::sync::{Lazy, OnceCell};
-use std::{mem, path::PathBuf, sync::Arc};
+use std::{
+    mem,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use uuid::Uuid;
+
+const WATCHED_EXTENSIONS: &[&str] = &["tex", "bib", "sty", "cls", "aux", "log"];
+
+const INDEX_CHUNK_SIZE: usize = 16;
 
 pub struct LatexLspServer<C> {
     distro: Arc<dyn Distribution>,
@@ -37,10 +54,12 @@ pub struct LatexLspServer<C> {
     client_capabilities: OnceCell<Arc<ClientCapabilities>>,
     client_info: OnceCell<Option<ClientInfo>>,
     current_dir: Arc<PathBuf>,
+    cache_dir: Option<PathBuf>,
     config_manager: OnceCell<ConfigManager<C>>,
     action_manager: ActionManager,
     workspace: Workspace,
     build_provider: BuildProvider<C>,
+    code_action_provider: CodeActionProvider,
     completion_provider: CompletionProvider,
     definition_provider: DefinitionProvider,
     folding_provider: FoldingProvider,
@@ -52,23 +71,36 @@ pub struct LatexLspServer<C> {
     symbol_provider: SymbolProvider,
     hover_provider: HoverProvider,
     diagnostics_manager: DiagnosticsManager,
+    synctex_cache: SyncTexCache,
     last_position_by_uri: CHashMap<Uri, Position>,
+    is_watching_files: AtomicBool,
+    is_pulling_diagnostics: AtomicBool,
 }
 
 #[jsonrpc_server]
 impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
-    pub fn new(distro: Arc<dyn Distribution>, client: Arc<C>, current_dir: Arc<PathBuf>) -> Self {
-        let workspace = Workspace::new(distro.clone(), Arc::clone(&current_dir));
+    pub fn new(
+        distro: Arc<dyn Distribution>,
+        client: Arc<C>,
+        current_dir: Arc<PathBuf>,
+        cache_dir: Option<PathBuf>,
+    ) -> Self {
+        let cache_path = cache_dir
+            .as_ref()
+            .map(|dir| document_cache::cache_path(dir, &current_dir));
+        let workspace = Workspace::new(distro.clone(), cache_path);
         Self {
             distro,
             client: Arc::clone(&client),
             client_capabilities: OnceCell::new(),
             client_info: OnceCell::new(),
             current_dir,
+            cache_dir,
             config_manager: OnceCell::new(),
             action_manager: ActionManager::default(),
             workspace,
             build_provider: BuildProvider::new(client),
+            code_action_provider: CodeActionProvider::new(),
             completion_provider: CompletionProvider::new(),
             definition_provider: DefinitionProvider::new(),
             folding_provider: FoldingProvider::new(),
@@ -80,7 +112,10 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
             symbol_provider: SymbolProvider::new(),
             hover_provider: HoverProvider::new(),
             diagnostics_manager: DiagnosticsManager::default(),
+            synctex_cache: SyncTexCache::new(),
             last_position_by_uri: CHashMap::new(),
+            is_watching_files: AtomicBool::new(false),
+            is_pulling_diagnostics: AtomicBool::new(false),
         }
     }
 
@@ -99,7 +134,18 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
     }
 
     #[jsonrpc_method("initialize", kind = "request")]
-    pub async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+    pub async fn initialize(&self, params: serde_json::Value) -> Result<InitializeResult> {
+        let pull_diagnostics_capabilities: PullDiagnosticsCapabilities = params
+            .get("capabilities")
+            .and_then(|caps| serde_json::from_value(caps.clone()).ok())
+            .unwrap_or_default();
+        self.is_pulling_diagnostics.store(
+            pull_diagnostics_capabilities.has_pull_diagnostics_support(),
+            Ordering::SeqCst,
+        );
+
+        let params: InitializeParams =
+            serde_json::from_value(params).map_err(|why| why.to_string())?;
         self.client_capabilities
             .set(Arc::new(params.capabilities))
             .expect("initialize was called two times");
@@ -111,8 +157,20 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         let _ = self.config_manager.set(ConfigManager::new(
             Arc::clone(&self.client),
             self.client_capabilities(),
+            Arc::clone(&self.current_dir),
         ));
 
+        let folder_uris = match params.workspace_folders {
+            Some(folders) => folders
+                .into_iter()
+                .map(|folder| folder.uri.into())
+                .collect(),
+            None => params.root_uri.into_iter().map(Into::into).collect(),
+        };
+        self.config_manager()
+            .update_folders(folder_uris, Vec::new())
+            .await;
+
         let capabilities = ServerCapabilities {
             text_document_sync: Some(TextDocumentSyncCapability::Options(
                 TextDocumentSyncOptions {
@@ -153,6 +211,22 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
                 work_done_progress_options: WorkDoneProgressOptions::default(),
             }),
             folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+            workspace: Some(WorkspaceCapability {
+                workspace_folders: Some(WorkspaceFolderCapability {
+                    supported: Some(true),
+                    change_notifications: Some(WorkspaceFolderCapabilityChangeNotifications::Bool(
+                        true,
+                    )),
+                }),
+            }),
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: CleanTarget::ALL
+                    .iter()
+                    .map(|target| target.command().into())
+                    .collect(),
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            }),
             ..ServerCapabilities::default()
         };
 
@@ -171,11 +245,13 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         self.action_manager.push(Action::PullConfiguration).await;
         self.action_manager.push(Action::RegisterCapabilities).await;
         self.action_manager.push(Action::LoadDistribution).await;
+        self.action_manager.push(Action::IndexWorkspace).await;
         self.action_manager.push(Action::PublishDiagnostics).await;
     }
 
     #[jsonrpc_method("shutdown", kind = "request")]
     pub async fn shutdown(&self, _params: ()) -> Result<()> {
+        self.workspace.prune_cache().await;
         Ok(())
     }
 
@@ -193,9 +269,11 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         self.action_manager
             .push(Action::DetectRoot(uri.clone().into()))
             .await;
+        self.queue_detect_children().await;
         self.action_manager
             .push(Action::RunLinter(uri.into(), LintReason::Save))
             .await;
+        self.action_manager.push(Action::IndexComponents).await;
         self.action_manager.push(Action::PublishDiagnostics).await;
     }
 
@@ -205,7 +283,12 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         for change in params.content_changes {
             let uri = params.text_document.uri.clone();
             self.workspace
-                .update(uri.into(), change.text, &options)
+                .update(
+                    uri.into(),
+                    params.text_document.version,
+                    change.text,
+                    &options,
+                )
                 .await;
         }
         self.action_manager
@@ -214,6 +297,7 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
                 LintReason::Change,
             ))
             .await;
+        self.queue_detect_children().await;
         self.action_manager.push(Action::PublishDiagnostics).await;
     }
 
@@ -229,11 +313,15 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
                 LintReason::Save,
             ))
             .await;
+        self.action_manager.push(Action::IndexComponents).await;
         self.action_manager.push(Action::PublishDiagnostics).await;
     }
 
     #[jsonrpc_method("textDocument/didClose", kind = "notification")]
-    pub async fn did_close(&self, _params: DidCloseTextDocumentParams) {}
+    pub async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.workspace.close(&params.text_document.uri.into()).await;
+        self.action_manager.push(Action::CollectGarbage).await;
+    }
 
     #[jsonrpc_method("workspace/didChangeConfiguration", kind = "notification")]
     pub async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
@@ -243,11 +331,87 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         self.workspace.reparse(&options).await;
     }
 
+    #[jsonrpc_method("workspace/didChangeWorkspaceFolders", kind = "notification")]
+    pub async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        let added = params.event.added.into_iter();
+        let removed = params.event.removed.into_iter();
+        let config_manager = self.config_manager();
+        config_manager
+            .update_folders(
+                added.map(|folder| folder.uri.into()).collect(),
+                removed.map(|folder| folder.uri.into()).collect(),
+            )
+            .await;
+        let options = config_manager.get().await;
+        self.workspace.reparse(&options).await;
+        self.action_manager.push(Action::PullConfiguration).await;
+    }
+
+    #[jsonrpc_method("workspace/didChangeWatchedFiles", kind = "notification")]
+    pub async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let options = self.config_manager().get().await;
+        let mut has_deletions = false;
+        for change in params.changes {
+            let uri: Uri = change.uri.into();
+            let snapshot = self.workspace.get().await;
+            let should_load = match change.typ {
+                FileChangeType::Created => {
+                    snapshot.find(&uri).is_none()
+                        && (snapshot.expand(&options).contains(&uri)
+                            || Workspace::is_indexed(&uri, &options))
+                }
+                FileChangeType::Changed => snapshot.find(&uri).is_some() && !snapshot.is_open(&uri),
+                FileChangeType::Deleted => {
+                    has_deletions = true;
+                    if self.workspace.remove(&uri).await {
+                        self.clear_diagnostics(uri.clone()).await;
+                    }
+                    false
+                }
+            };
+
+            if should_load {
+                if let Ok(path) = uri.to_file_path() {
+                    let _ = self.workspace.load(&path, &options).await;
+                }
+            }
+        }
+
+        self.action_manager.push(Action::DetectChildren).await;
+        if has_deletions {
+            self.action_manager.push(Action::CollectGarbage).await;
+        }
+        self.action_manager.push(Action::PublishDiagnostics).await;
+    }
+
     #[jsonrpc_method("window/workDoneProgress/cancel", kind = "notification")]
     pub async fn work_done_progress_cancel(&self, params: WorkDoneProgressCancelParams) {
         self.build_provider.cancel(params.token).await;
     }
 
+    #[jsonrpc_method("textDocument/diagnostic", kind = "request")]
+    pub async fn diagnostic(
+        &self,
+        params: DocumentDiagnosticParams,
+    ) -> Result<DocumentDiagnosticReport> {
+        let uri: Uri = params.text_document.uri.into();
+        let snapshot = self.workspace.get().await;
+        let diagnostics = match snapshot.find(&uri) {
+            Some(doc) => self.diagnostics_manager.get(&doc).await,
+            None => Vec::new(),
+        };
+
+        let result_id = format!("{:x}", DiagnosticsManager::hash(&diagnostics));
+        if params.previous_result_id.as_ref() == Some(&result_id) {
+            Ok(DocumentDiagnosticReport::Unchanged { result_id })
+        } else {
+            Ok(DocumentDiagnosticReport::Full {
+                result_id: Some(result_id),
+                items: diagnostics,
+            })
+        }
+    }
+
     #[jsonrpc_method("textDocument/completion", kind = "request")]
     pub async fn completion(&self, params: CompletionParams) -> Result<CompletionList> {
         let req = self
@@ -366,15 +530,8 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         let client_capabilities = self.client_capabilities();
         let snapshot = self.workspace.get().await;
         let options = self.config_manager().get().await;
-        let symbols = workspace_symbols(
-            distro,
-            client_capabilities,
-            snapshot,
-            &options,
-            Arc::clone(&self.current_dir),
-            &params,
-        )
-        .await;
+        let symbols =
+            workspace_symbols(distro, client_capabilities, snapshot, &options, &params).await;
         Ok(symbols)
     }
 
@@ -392,8 +549,6 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
             &req.client_capabilities,
             req.snapshot(),
             &req.current().uri,
-            &req.options,
-            &req.current_dir,
             symbols.into_iter().map(Into::into).collect(),
         );
         Ok(response)
@@ -495,6 +650,14 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         Ok(self.folding_provider.execute(&req).await)
     }
 
+    #[jsonrpc_method("textDocument/codeAction", kind = "request")]
+    pub async fn code_action(&self, params: CodeActionParams) -> Result<CodeActionResponse> {
+        let req = self
+            .make_feature_request(params.text_document.as_uri(), params)
+            .await?;
+        Ok(self.code_action_provider.execute(&req).await)
+    }
+
     #[jsonrpc_method("textDocument/build", kind = "request")]
     pub async fn build(&self, params: BuildParams) -> Result<BuildResult> {
         let req = self
@@ -538,12 +701,99 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
             &req.current().uri,
             req.params.position.line,
             &req.options,
-            &self.current_dir,
+            &req.current_dir,
         )
         .await
         .ok_or_else(|| "Unable to execute forward search".into())
     }
 
+    #[jsonrpc_method("texlab/buildLog", kind = "request")]
+    pub async fn build_log(&self, params: BuildLogParams) -> Result<Option<BuildLog>> {
+        let uri = params.text_document.as_uri();
+        let options = self.pull_configuration().await;
+        let (options, current_dir) = options.get(&uri);
+        let snapshot = self.workspace.get().await;
+        Ok(build_log(&snapshot, &uri, options, current_dir).await)
+    }
+
+    #[jsonrpc_method("texlab/forwardSearch", kind = "request")]
+    pub async fn synctex_forward_search(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PdfLocation>> {
+        let req = self
+            .make_feature_request(params.text_document.as_uri(), params)
+            .await?;
+
+        Ok(synctex::forward_search(
+            &req.view.snapshot,
+            &req.current().uri,
+            req.params.position.line,
+            &req.options,
+            &req.current_dir,
+            &self.synctex_cache,
+        )
+        .await)
+    }
+
+    #[jsonrpc_method("texlab/inverseSearch", kind = "request")]
+    pub async fn synctex_inverse_search(
+        &self,
+        params: InverseSearchParams,
+    ) -> Result<Option<Location>> {
+        let options = self.pull_configuration().await;
+        let snapshot = self.workspace.get().await;
+        Ok(synctex::inverse_search(
+            &snapshot,
+            &options,
+            &params.text_document.as_uri(),
+            params.page,
+            params.x,
+            params.y,
+            &self.synctex_cache,
+        )
+        .await)
+    }
+
+    #[jsonrpc_method("workspace/executeCommand", kind = "request")]
+    pub async fn execute_command(&self, params: ExecuteCommandParams) -> Result<serde_json::Value> {
+        let command = params.command;
+        let target = CleanTarget::by_command(&command)
+            .ok_or_else(|| format!("Unknown command: {}", command))?;
+
+        let text_document: TextDocumentIdentifier = params
+            .arguments
+            .into_iter()
+            .next()
+            .and_then(|arg| serde_json::from_value(arg).ok())
+            .ok_or_else(|| format!("Missing text document: {}", command))?;
+
+        let uri = text_document.as_uri();
+        let options = self.pull_configuration().await;
+        let (options, current_dir) = options.get(&uri);
+        let snapshot = self.workspace.get().await;
+        let deleted_files = clean(&snapshot, &uri, target, options, current_dir).await;
+
+        for uri in &deleted_files {
+            let params = LogMessageParams {
+                typ: MessageType::Log,
+                message: format!("Deleted {}", uri),
+            };
+            self.client.log_message(params).await;
+        }
+
+        let params = ShowMessageParams {
+            typ: MessageType::Info,
+            message: format!("Deleted {} file(s)", deleted_files.len()),
+        };
+        self.client.show_message(params).await;
+
+        let result = CleanResult {
+            deleted_files: deleted_files.into_iter().map(Into::into).collect(),
+        };
+        Ok(serde_json::to_value(result).unwrap())
+    }
+
     #[jsonrpc_method("$/detectRoot", kind = "request")]
     pub async fn detect_root(&self, params: TextDocumentIdentifier) -> Result<()> {
         let options = self.config_manager().get().await;
@@ -553,16 +803,17 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
 
     async fn make_feature_request<P>(&self, uri: Uri, params: P) -> Result<FeatureRequest<P>> {
         let options = self.pull_configuration().await;
+        let (options, current_dir) = options.get(&uri);
         let snapshot = self.workspace.get().await;
         let client_capabilities = self.client_capabilities();
         match snapshot.find(&uri) {
             Some(current) => Ok(FeatureRequest {
                 params,
-                view: DocumentView::analyze(snapshot, current, &options, &self.current_dir),
+                view: DocumentView::analyze(snapshot, current),
                 distro: self.distro.clone(),
                 client_capabilities,
-                options,
-                current_dir: Arc::clone(&self.current_dir),
+                options: options.clone(),
+                current_dir: Arc::new(current_dir.to_owned()),
             }),
             None => {
                 let msg = format!("Unknown document: {}", uri);
@@ -571,7 +822,7 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         }
     }
 
-    async fn pull_configuration(&self) -> Options {
+    async fn pull_configuration(&self) -> ScopedOptions {
         let config_manager = self.config_manager();
         let has_changed = config_manager.pull().await;
         let options = config_manager.get().await;
@@ -585,13 +836,18 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         let snapshot = self.workspace.get().await;
         let options = self.config_manager().get().await;
 
-        for doc in snapshot.0.iter().filter(|doc| doc.uri.scheme() == "file") {
+        for doc in snapshot
+            .documents()
+            .iter()
+            .filter(|doc| doc.uri.scheme() == "file")
+        {
             if let DocumentContent::Latex(table) = &doc.content {
                 if table.is_standalone {
+                    let (options, current_dir) = options.get(&doc.uri);
                     match self
                         .diagnostics_manager
                         .build
-                        .update(&snapshot, &doc.uri, &options, &self.current_dir)
+                        .update(&snapshot, &doc.uri, options, current_dir)
                         .await
                     {
                         Ok(true) => self.action_manager.push(Action::PublishDiagnostics).await,
@@ -605,6 +861,204 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
         }
     }
 
+    async fn register_file_watchers(&self) {
+        if !self.client_capabilities().has_file_watching_support() {
+            return;
+        }
+
+        let watchers = WATCHED_EXTENSIONS
+            .iter()
+            .map(|extension| FileSystemWatcher {
+                glob_pattern: format!("**/*.{}", extension),
+                kind: None,
+            })
+            .collect();
+
+        let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+        let registration = Registration {
+            id: "watch-files".into(),
+            method: "workspace/didChangeWatchedFiles".into(),
+            register_options: Some(serde_json::to_value(options).unwrap()),
+        };
+        let params = RegistrationParams {
+            registrations: vec![registration],
+        };
+
+        match self.client.register_capability(params).await {
+            Ok(()) => self.is_watching_files.store(true, Ordering::SeqCst),
+            Err(why) => error!(
+                "Failed to register \"workspace/didChangeWatchedFiles\": {}",
+                why.message
+            ),
+        }
+    }
+
+    async fn register_diagnostic_provider(&self) {
+        if !self.is_pulling_diagnostics.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let options = DiagnosticRegistrationOptions {
+            document_selector: None,
+            identifier: Some("texlab".into()),
+            inter_file_dependencies: true,
+            workspace_diagnostics: false,
+        };
+        let registration = Registration {
+            id: "pull-diagnostics".into(),
+            method: "textDocument/diagnostic".into(),
+            register_options: Some(serde_json::to_value(options).unwrap()),
+        };
+        let params = RegistrationParams {
+            registrations: vec![registration],
+        };
+
+        if let Err(why) = self.client.register_capability(params).await {
+            warn!(
+                "Failed to register \"textDocument/diagnostic\": {}",
+                why.message
+            );
+        }
+    }
+
+    async fn index_workspace(&self) {
+        let options = self.config_manager().get().await;
+        let mut paths = Vec::new();
+        for folder in &options.folders {
+            let indexing = options
+                .folder_options(folder)
+                .latex
+                .as_ref()
+                .and_then(|opts| opts.indexing.clone())
+                .unwrap_or_default();
+
+            if indexing.on_startup() {
+                paths.append(&mut Workspace::find_files(
+                    &folder.path,
+                    &indexing.exclude(),
+                ));
+            }
+        }
+
+        if paths.is_empty() {
+            return;
+        }
+
+        let token = ProgressToken::String(format!("texlab-index-{}", Uuid::new_v4()));
+        let has_progress = self.client_capabilities().has_work_done_progress_support();
+        if has_progress {
+            let params = WorkDoneProgressCreateParams {
+                token: token.clone(),
+            };
+            if let Err(why) = self.client.work_done_progress_create(params).await {
+                warn!("Failed to create progress: {}", why.message);
+            }
+
+            let params = ProgressParams {
+                token: token.clone(),
+                value: ProgressParamsValue::WorkDone(WorkDoneProgress::Begin(
+                    WorkDoneProgressBegin {
+                        title: "Indexing".into(),
+                        cancellable: Some(false),
+                        message: None,
+                        percentage: Some(0.0),
+                    },
+                )),
+            };
+            self.client.progress(params).await;
+        }
+
+        let mut loaded = 0;
+        for chunk in paths.chunks(INDEX_CHUNK_SIZE) {
+            self.workspace.load_all(chunk, &options).await;
+            loaded += chunk.len();
+
+            if has_progress {
+                let params = ProgressParams {
+                    token: token.clone(),
+                    value: ProgressParamsValue::WorkDone(WorkDoneProgress::Report(
+                        WorkDoneProgressReport {
+                            cancellable: Some(false),
+                            message: Some(format!("{}/{} files", loaded, paths.len())),
+                            percentage: Some(loaded as f64 * 100.0 / paths.len() as f64),
+                        },
+                    )),
+                };
+                self.client.progress(params).await;
+            }
+        }
+        self.workspace.prune_cache().await;
+
+        if has_progress {
+            let params = ProgressParams {
+                token,
+                value: ProgressParamsValue::WorkDone(WorkDoneProgress::End(WorkDoneProgressEnd {
+                    message: None,
+                })),
+            };
+            self.client.progress(params).await;
+        }
+        info!("Indexed {} file(s)", paths.len());
+    }
+
+    async fn queue_detect_children(&self) {
+        // Without file watchers, the children are detected before every message anyway.
+        if self.is_watching_files.load(Ordering::SeqCst) {
+            self.action_manager.push(Action::DetectChildren).await;
+        }
+    }
+
+    async fn collect_garbage(&self) {
+        let options = self.config_manager().get().await;
+        for uri in self.workspace.collect_garbage(&options).await {
+            self.clear_diagnostics(uri).await;
+        }
+    }
+
+    async fn clear_diagnostics(&self, uri: Uri) {
+        self.diagnostics_manager.remove(&uri).await;
+        let params = PublishDiagnosticsParams {
+            uri: uri.into(),
+            diagnostics: Vec::new(),
+            version: None,
+        };
+        self.client.publish_diagnostics(params).await;
+    }
+
+    async fn publish_diagnostics(&self) {
+        let is_pulling = self.is_pulling_diagnostics.load(Ordering::SeqCst);
+        let mut needs_refresh = false;
+        let snapshot = self.workspace.get().await;
+        for doc in snapshot.documents() {
+            let diagnostics = self.diagnostics_manager.get(doc).await;
+            let current_snapshot = self.workspace.get().await;
+            let is_stale = current_snapshot
+                .find(&doc.uri)
+                .map_or(true, |current_doc| current_doc.version != doc.version);
+            if is_stale || !self.diagnostics_manager.has_changed(&doc.uri, &diagnostics) {
+                continue;
+            }
+
+            if is_pulling {
+                needs_refresh = true;
+                continue;
+            }
+
+            let params = PublishDiagnosticsParams {
+                uri: doc.uri.clone().into(),
+                diagnostics,
+                version: doc.version,
+            };
+            self.client.publish_diagnostics(params).await;
+        }
+
+        if needs_refresh {
+            if let Err(why) = self.client.diagnostic_refresh(()).await {
+                warn!("Failed to refresh diagnostics: {}", why.message);
+            }
+        }
+    }
+
     async fn load_distribution(&self) {
         info!("Detected TeX distribution: {}", self.distro.kind());
         if self.distro.kind() == DistributionKind::Unknown {
@@ -617,7 +1071,7 @@ impl<C: LspClient + Send + Sync + 'static> LatexLspServer<C> {
             self.client.show_message(params).await;
         }
 
-        if let Err(why) = self.distro.load().await {
+        if let Err(why) = self.distro.load(self.cache_dir.as_deref()).await {
             let message = match why {
                 KpsewhichError::NotInstalled | KpsewhichError::InvalidOutput => {
                     "An error occurred while executing `kpsewhich`.\
@@ -650,21 +1104,48 @@ impl<C: LspClient + Send + Sync + 'static> Middleware for LatexLspServer<C> {
     async fn before_message(&self) {
         if let Some(config_manager) = self.config_manager.get() {
             let options = config_manager.get().await;
-            self.workspace.detect_children(&options).await;
-            self.workspace.reparse_all_if_newer(&options).await;
+            if self.is_watching_files.load(Ordering::SeqCst) {
+                // The file watchers only cover the workspace folders.
+                self.workspace
+                    .reparse_all_if_newer(&options, |uri| options.folder(uri).is_none())
+                    .await;
+            } else {
+                self.workspace.detect_children(&options).await;
+                self.workspace
+                    .reparse_all_if_newer(&options, |_| true)
+                    .await;
+            }
         }
     }
 
-    async fn after_message(&self) {
+    async fn after_message(self: Arc<Self>) {
         self.update_build_diagnostics().await;
+        let guard = self.action_manager.lock().await;
+        let mut builds = Vec::new();
         for action in self.action_manager.take().await {
             match action {
                 Action::LoadDistribution => {
-                    self.load_distribution().await;
+                    if self.distro.load_cached(self.cache_dir.as_deref()).await {
+                        // Validate the cached database without delaying the other actions.
+                        let server = Arc::clone(&self);
+                        tokio::spawn(async move {
+                            let resolver = server.distro.resolver().await;
+                            server.load_distribution().await;
+                            if *server.distro.resolver().await != *resolver {
+                                let options = server.config_manager().get().await;
+                                server.workspace.reparse(&options).await;
+                                server.publish_diagnostics().await;
+                            }
+                        });
+                    } else {
+                        self.load_distribution().await;
+                    }
                 }
                 Action::RegisterCapabilities => {
                     let config_manager = self.config_manager();
                     config_manager.register().await;
+                    self.register_file_watchers().await;
+                    self.register_diagnostic_provider().await;
                 }
                 Action::PullConfiguration => {
                     self.pull_configuration().await;
@@ -673,40 +1154,51 @@ impl<C: LspClient + Send + Sync + 'static> Middleware for LatexLspServer<C> {
                     let options = self.config_manager().get().await;
                     let _ = self.workspace.detect_root(&uri, &options).await;
                 }
+                Action::IndexWorkspace => {
+                    let server = Arc::clone(&self);
+                    tokio::spawn(async move {
+                        server.index_workspace().await;
+                        server.publish_diagnostics().await;
+                    });
+                }
+                Action::DetectChildren => {
+                    let options = self.config_manager().get().await;
+                    self.workspace.detect_children(&options).await;
+                }
+                Action::CollectGarbage => {
+                    self.collect_garbage().await;
+                }
                 Action::PublishDiagnostics => {
-                    let snapshot = self.workspace.get().await;
-                    for doc in &snapshot.0 {
-                        let diagnostics = self.diagnostics_manager.get(doc).await;
-                        let params = PublishDiagnosticsParams {
-                            uri: doc.uri.clone().into(),
-                            diagnostics,
-                            version: None,
-                        };
-                        self.client.publish_diagnostics(params).await;
-                    }
+                    self.publish_diagnostics().await;
                 }
                 Action::Build(uri) => {
-                    let options = self
-                        .config_manager()
+                    builds.push(uri);
+                }
+                Action::IndexComponents => {
+                    let file_names = self
+                        .workspace
                         .get()
                         .await
-                        .latex
-                        .and_then(|opts| opts.build)
-                        .unwrap_or_default();
-
-                    if options.on_save() {
-                        let text_document = TextDocumentIdentifier::new(uri.into());
-                        self.build(BuildParams { text_document }).await.unwrap();
-                    }
+                        .documents()
+                        .iter()
+                        .filter_map(|doc| doc.content.as_latex())
+                        .flat_map(|table| table.components.iter().cloned())
+                        .collect();
+
+                    let resolver = self.distro.resolver().await;
+                    let cache_path = self
+                        .cache_dir
+                        .as_ref()
+                        .map(|dir| dir.join("components.json"));
+                    tokio::spawn(async move {
+                        COMPONENT_INDEX
+                            .update(resolver, file_names, cache_path.as_deref())
+                            .await;
+                    });
                 }
                 Action::RunLinter(uri, reason) => {
-                    let options = self
-                        .config_manager()
-                        .get()
-                        .await
-                        .latex
-                        .and_then(|opts| opts.lint)
-                        .unwrap_or_default();
+                    let scoped_options = self.config_manager().get().await;
+                    let (options, root_directory) = lint_options(&scoped_options, &uri);
 
                     let should_lint = match reason {
                         LintReason::Change => options.on_change(),
@@ -717,13 +1209,42 @@ impl<C: LspClient + Send + Sync + 'static> Middleware for LatexLspServer<C> {
                         let snapshot = self.workspace.get().await;
                         if let Some(doc) = snapshot.find(&uri) {
                             if let DocumentContent::Latex(_) = &doc.content {
-                                self.diagnostics_manager.latex.update(&uri, &doc.text).await;
+                                self.diagnostics_manager
+                                    .latex
+                                    .update(&doc, &options, &root_directory)
+                                    .await;
                             }
                         }
                     }
                 }
             }
         }
+        drop(guard);
+
+        if builds.is_empty() {
+            return;
+        }
+
+        // Builds can take a while, so they must not hold up the following messages.
+        tokio::spawn(async move {
+            for uri in builds {
+                let options = self
+                    .config_manager()
+                    .get()
+                    .await
+                    .get(&uri)
+                    .0
+                    .latex
+                    .as_ref()
+                    .and_then(|opts| opts.build.clone())
+                    .unwrap_or_default();
+
+                if options.on_save() {
+                    let text_document = TextDocumentIdentifier::new(uri.into());
+                    self.build(BuildParams { text_document }).await.unwrap();
+                }
+            }
+        });
     }
 }
 
@@ -739,14 +1260,19 @@ enum Action {
     RegisterCapabilities,
     PullConfiguration,
     DetectRoot(Uri),
+    IndexWorkspace,
+    DetectChildren,
+    CollectGarbage,
     PublishDiagnostics,
     Build(Uri),
+    IndexComponents,
     RunLinter(Uri, LintReason),
 }
 
 #[derive(Debug, Default)]
 struct ActionManager {
     actions: Mutex<Vec<Action>>,
+    processing: Mutex<()>,
 }
 
 impl ActionManager {
@@ -755,6 +1281,10 @@ impl ActionManager {
         actions.push(action);
     }
 
+    pub async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.processing.lock().await
+    }
+
     pub async fn take(&self) -> Vec<Action> {
         let mut actions = self.actions.lock().await;
         mem::replace(&mut *actions, Vec::new())
@@ -7,12 +7,11 @@ use super::types::{LatexSymbol, LatexSymbolKind};
 use crate::{
     feature::{DocumentView, FeatureProvider, FeatureRequest},
     outline::{Outline, OutlineContext, OutlineContextItem},
-    protocol::{DocumentSymbolParams, Options, Position, Range, RangeExt},
+    protocol::{DocumentSymbolParams, Position, Range, RangeExt},
     syntax::{latex, CharStream, LatexLabelKind, SyntaxNode},
     workspace::DocumentContent,
 };
 use async_trait::async_trait;
-use std::path::Path;
 
 fn label_name(table: &latex::SymbolTable, label: Option<&latex::Label>) -> Option<String> {
     label.map(|label| label.names(&table)[0].text().to_owned())
@@ -39,8 +38,7 @@ impl FeatureProvider for LatexSectionSymbolProvider {
     async fn execute<'a>(&'a self, req: &'a FeatureRequest<Self::Params>) -> Self::Output {
         let mut symbols = Vec::new();
         if let DocumentContent::Latex(table) = &req.current().content {
-            let mut section_tree =
-                build_section_tree(&req.view, table, &req.options, &req.current_dir);
+            let mut section_tree = build_section_tree(&req.view, table);
             for symbol in enumeration::symbols(&req.view, table) {
                 section_tree.insert_symbol(&symbol);
             }
@@ -72,14 +70,12 @@ impl FeatureProvider for LatexSectionSymbolProvider {
 pub fn build_section_tree<'a>(
     view: &'a DocumentView,
     table: &'a latex::SymbolTable,
-    options: &'a Options,
-    current_dir: &'a Path,
 ) -> LatexSectionTree<'a> {
     let mut section_tree = LatexSectionTree::from(table);
     section_tree.set_full_text(&view.current.text);
     let end_position = compute_end_position(table, &view.current.text);
     LatexSectionNode::set_full_range(&mut section_tree.children, table, end_position);
-    let outline = Outline::analyze(view, options, current_dir);
+    let outline = Outline::analyze(view);
     for child in &mut section_tree.children {
         child.set_label(view, &outline);
     }
@@ -14,18 +14,14 @@ use crate::{
     feature::{ConcatProvider, DocumentView, FeatureProvider, FeatureRequest},
     protocol::{
         ClientCapabilities, ClientCapabilitiesExt, DocumentSymbolParams, DocumentSymbolResponse,
-        Options, PartialResultParams, SymbolInformation, TextDocumentIdentifier, Uri,
+        PartialResultParams, ScopedOptions, SymbolInformation, TextDocumentIdentifier, Uri,
         WorkDoneProgressParams, WorkspaceSymbolParams,
     },
     tex::Distribution,
     workspace::Snapshot,
 };
 use async_trait::async_trait;
-use std::{
-    cmp::Reverse,
-    path::{Path, PathBuf},
-    sync::Arc,
-};
+use std::{cmp::Reverse, sync::Arc};
 
 pub struct SymbolProvider {
     provider: ConcatProvider<DocumentSymbolParams, LatexSymbol>,
@@ -63,8 +59,6 @@ pub fn document_symbols(
     client_capabilities: &ClientCapabilities,
     snapshot: &Snapshot,
     uri: &Uri,
-    options: &Options,
-    current_dir: &Path,
     symbols: Vec<LatexSymbol>,
 ) -> DocumentSymbolResponse {
     if client_capabilities.has_hierarchical_document_symbol_support() {
@@ -78,7 +72,7 @@ pub fn document_symbols(
             .into_iter()
             .map(|symbol| symbol.into_symbol_info(uri.clone()))
             .collect();
-        sort_symbols(snapshot, options, &current_dir, &mut buffer);
+        sort_symbols(snapshot, &mut buffer);
         DocumentSymbolResponse::Flat(buffer)
     }
 }
@@ -92,31 +86,26 @@ pub async fn workspace_symbols<'a>(
     distro: Arc<dyn Distribution>,
     client_capabilities: Arc<ClientCapabilities>,
     snapshot: Arc<Snapshot>,
-    options: &'a Options,
-    current_dir: Arc<PathBuf>,
+    options: &'a ScopedOptions,
     params: &'a WorkspaceSymbolParams,
 ) -> Vec<SymbolInformation> {
     let provider = SymbolProvider::new();
     let mut symbols = Vec::new();
 
-    for doc in &snapshot.0 {
+    for doc in snapshot.documents() {
         let uri: Uri = doc.uri.clone();
+        let (options, current_dir) = options.get(&uri);
         let req = FeatureRequest {
             params: DocumentSymbolParams {
                 text_document: TextDocumentIdentifier::new(uri.clone().into()),
                 work_done_progress_params: WorkDoneProgressParams::default(),
                 partial_result_params: PartialResultParams::default(),
             },
-            view: DocumentView::analyze(
-                Arc::clone(&snapshot),
-                Arc::clone(&doc),
-                &options,
-                &current_dir,
-            ),
+            view: DocumentView::analyze(Arc::clone(&snapshot), Arc::clone(&doc)),
             distro: distro.clone(),
             client_capabilities: Arc::clone(&client_capabilities),
             options: options.clone(),
-            current_dir: Arc::clone(&current_dir),
+            current_dir: Arc::new(current_dir.to_owned()),
         };
 
         let mut buffer = Vec::new();
@@ -151,17 +140,12 @@ pub async fn workspace_symbols<'a>(
             filtered.push(symbol.info);
         }
     }
-    sort_symbols(&snapshot, options, &current_dir, &mut filtered);
+    sort_symbols(&snapshot, &mut filtered);
     filtered
 }
 
-fn sort_symbols(
-    snapshot: &Snapshot,
-    options: &Options,
-    current_dir: &Path,
-    symbols: &mut Vec<SymbolInformation>,
-) {
-    let ordering = ProjectOrdering::analyze(snapshot, options, current_dir);
+fn sort_symbols(snapshot: &Snapshot, symbols: &mut Vec<SymbolInformation>) {
+    let ordering = ProjectOrdering::analyze(snapshot);
     symbols.sort_by(|left, right| {
         let left_key = (
             ordering.get(&Uri::from(left.location.uri.clone())),
@@ -1,9 +1,9 @@
 use crate::{
-    protocol::{Options, Uri},
+    protocol::Uri,
     workspace::{Document, DocumentContent, Snapshot},
 };
 use petgraph::{algo::tarjan_scc, Directed, Graph};
-use std::{collections::HashSet, path::Path, sync::Arc, usize};
+use std::{collections::HashSet, sync::Arc, usize};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ProjectOrdering {
@@ -18,9 +18,9 @@ impl ProjectOrdering {
             .unwrap_or(usize::MAX)
     }
 
-    pub fn analyze(snapshot: &Snapshot, options: &Options, current_dir: &Path) -> Self {
+    pub fn analyze(snapshot: &Snapshot) -> Self {
         let mut ordering = Vec::new();
-        let comps = Self::connected_components(snapshot, options, current_dir);
+        let comps = Self::connected_components(snapshot);
         for comp in comps {
             let graph = Self::build_dependency_graph(&comp);
 
@@ -51,19 +51,15 @@ impl ProjectOrdering {
         Self { ordering }
     }
 
-    fn connected_components(
-        snapshot: &Snapshot,
-        options: &Options,
-        current_dir: &Path,
-    ) -> Vec<Vec<Arc<Document>>> {
+    fn connected_components(snapshot: &Snapshot) -> Vec<Vec<Arc<Document>>> {
         let mut comps = Vec::new();
         let mut visited = HashSet::new();
-        for root in &snapshot.0 {
+        for root in snapshot.documents() {
             if !visited.insert(root.uri.clone()) {
                 continue;
             }
 
-            let comp = snapshot.relations(&root.uri, options, current_dir);
+            let comp = snapshot.relations(&root.uri);
             for document in &comp {
                 visited.insert(document.uri.clone());
             }
@@ -100,6 +96,7 @@ impl ProjectOrdering {
 mod tests {
     use super::*;
     use crate::{
+        protocol::Options,
         tex::{Language, Resolver},
         workspace::DocumentParams,
     };
@@ -116,20 +113,26 @@ mod tests {
         }))
     }
 
+    fn create_snapshot(docs: Vec<Arc<Document>>) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        for doc in docs {
+            snapshot.insert(doc, &Options::default(), &env::current_dir().unwrap());
+        }
+        snapshot
+    }
+
     #[test]
     fn no_cycles() {
         let a = Uri::parse("http://example.com/a.tex").unwrap();
         let b = Uri::parse("http://example.com/b.tex").unwrap();
         let c = Uri::parse("http://example.com/c.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
+        let snapshot = create_snapshot(vec![
             create_simple_document(&a, Language::Latex, ""),
             create_simple_document(&b, Language::Latex, ""),
             create_simple_document(&c, Language::Latex, r#"\include{b}\include{a}"#),
-        ];
+        ]);
 
-        let current_dir = env::current_dir().unwrap();
-        let ordering = ProjectOrdering::analyze(&snapshot, &Options::default(), &current_dir);
+        let ordering = ProjectOrdering::analyze(&snapshot);
 
         assert_eq!(ordering.get(&a), 2);
         assert_eq!(ordering.get(&b), 1);
@@ -141,15 +144,13 @@ mod tests {
         let a = Uri::parse("http://example.com/a.tex").unwrap();
         let b = Uri::parse("http://example.com/b.tex").unwrap();
         let c = Uri::parse("http://example.com/c.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
+        let snapshot = create_snapshot(vec![
             create_simple_document(&a, Language::Latex, r#"\include{b}"#),
             create_simple_document(&b, Language::Latex, r#"\include{a}"#),
             create_simple_document(&c, Language::Latex, r#"\include{a}"#),
-        ];
+        ]);
 
-        let current_dir = env::current_dir().unwrap();
-        let ordering = ProjectOrdering::analyze(&snapshot, &Options::default(), &current_dir);
+        let ordering = ProjectOrdering::analyze(&snapshot);
 
         assert_eq!(ordering.get(&a), 1);
         assert_eq!(ordering.get(&b), 2);
@@ -162,16 +163,14 @@ mod tests {
         let b = Uri::parse("http://example.com/b.tex").unwrap();
         let c = Uri::parse("http://example.com/c.tex").unwrap();
         let d = Uri::parse("http://example.com/d.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
+        let snapshot = create_snapshot(vec![
             create_simple_document(&a, Language::Latex, r#"\include{b}"#),
             create_simple_document(&b, Language::Latex, ""),
             create_simple_document(&c, Language::Latex, ""),
             create_simple_document(&d, Language::Latex, r#"\include{c}"#),
-        ];
+        ]);
 
-        let current_dir = env::current_dir().unwrap();
-        let ordering = ProjectOrdering::analyze(&snapshot, &Options::default(), &current_dir);
+        let ordering = ProjectOrdering::analyze(&snapshot);
 
         assert_eq!(ordering.get(&a), 0);
         assert_eq!(ordering.get(&b), 1);
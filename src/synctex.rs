@@ -0,0 +1,516 @@
+use crate::{
+    output::OutputLocations,
+    protocol::{Location, Options, PdfLocation, PdfRect, Range, RangeExt, ScopedOptions, Uri},
+    workspace::{DocumentContent, Snapshot},
+};
+use flate2::read::GzDecoder;
+use futures::lock::Mutex;
+use log::warn;
+use std::{
+    collections::HashMap,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+use tokio::fs;
+
+const SP_PER_BP: f64 = 65781.76;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SyncTexRecordKind {
+    VBox,
+    HBox,
+    Point,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SyncTexRecord {
+    pub kind: SyncTexRecordKind,
+    pub tag: usize,
+    pub line: u64,
+    pub page: u64,
+    pub rect: PdfRect,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SyncTex {
+    pub inputs: HashMap<usize, PathBuf>,
+    pub records: Vec<SyncTexRecord>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Scale {
+    unit: f64,
+    magnification: f64,
+    x_offset: f64,
+    y_offset: f64,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self {
+            unit: 1.0,
+            magnification: 1000.0,
+            x_offset: 0.0,
+            y_offset: 0.0,
+        }
+    }
+}
+
+impl Scale {
+    fn length(&self, value: f64) -> f64 {
+        value * self.unit * self.magnification / 1000.0 / SP_PER_BP
+    }
+
+    fn x(&self, value: f64) -> f64 {
+        self.length(value) + self.length(self.x_offset)
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        self.length(value) + self.length(self.y_offset)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Parser {
+    synctex: SyncTex,
+    scale: Scale,
+    page: u64,
+    boxes: Vec<PdfRect>,
+    last_values: [f64; 5],
+}
+
+impl Parser {
+    fn parse_line(&mut self, base_dir: &Path, line: &str) {
+        let mut chars = line.chars();
+        let first = match chars.next() {
+            Some(c) => c,
+            None => return,
+        };
+        let rest = chars.as_str();
+
+        match first {
+            '{' => {
+                self.page = rest.trim().parse().unwrap_or(self.page + 1);
+                self.boxes.clear();
+            }
+            '}' => self.boxes.clear(),
+            '[' | '(' | 'v' | 'h' => {
+                if let Some((tag, line, [x, y, width, height, depth])) = self.parse_record(rest) {
+                    let rect = PdfRect {
+                        x: self.scale.x(x),
+                        y: self.scale.y(y - height),
+                        width: self.scale.length(width),
+                        height: self.scale.length(height + depth),
+                    };
+                    let kind = if first == '[' || first == 'v' {
+                        SyncTexRecordKind::VBox
+                    } else {
+                        SyncTexRecordKind::HBox
+                    };
+                    self.push(kind, tag, line, rect);
+                    if first == '[' || first == '(' {
+                        self.boxes.push(rect);
+                    }
+                } else if first == '[' || first == '(' {
+                    self.boxes.push(PdfRect::default());
+                }
+            }
+            ']' | ')' => {
+                self.boxes.pop();
+            }
+            'x' | 'k' | 'g' | '$' => {
+                if let Some((tag, line, [x, y, width, _, _])) = self.parse_record(rest) {
+                    let parent = self.boxes.last().copied().unwrap_or_default();
+                    let height = parent.height.max(0.0);
+                    let rect = PdfRect {
+                        x: self.scale.x(x),
+                        y: if parent.height > 0.0 {
+                            parent.y
+                        } else {
+                            self.scale.y(y)
+                        },
+                        width: self.scale.length(width).max(0.0),
+                        height,
+                    };
+                    self.push(SyncTexRecordKind::Point, tag, line, rect);
+                }
+            }
+            _ => self.parse_header(base_dir, line),
+        }
+    }
+
+    fn parse_header(&mut self, base_dir: &Path, line: &str) {
+        let index = match line.find(':') {
+            Some(index) => index,
+            None => return,
+        };
+        let (name, value) = (&line[..index], &line[index + 1..]);
+        match name {
+            "Input" => {
+                if let Some(index) = value.find(':') {
+                    if let Ok(tag) = value[..index].parse() {
+                        let path = base_dir
+                            .join(&value[index + 1..])
+                            .components()
+                            .collect::<PathBuf>();
+                        self.synctex.inputs.insert(tag, path);
+                    }
+                }
+            }
+            "Unit" => self.scale.unit = value.trim().parse().unwrap_or(1.0),
+            "Magnification" => self.scale.magnification = value.trim().parse().unwrap_or(1000.0),
+            "X Offset" => self.scale.x_offset = value.trim().parse().unwrap_or(0.0),
+            "Y Offset" => self.scale.y_offset = value.trim().parse().unwrap_or(0.0),
+            _ => (),
+        }
+    }
+
+    fn parse_record(&mut self, text: &str) -> Option<(usize, u64, [f64; 5])> {
+        let mut parts = text.split(':');
+        let mut link = parts.next()?.split(',');
+        let tag = link.next()?.parse().ok()?;
+        let line = link.next()?.parse().ok()?;
+
+        let mut values = [0.0; 5];
+        let numbers = parts.flat_map(|part| part.split(','));
+        for (i, number) in numbers.take(5).enumerate() {
+            values[i] = if number == "=" {
+                self.last_values[i]
+            } else {
+                number.parse().ok()?
+            };
+            self.last_values[i] = values[i];
+        }
+        Some((tag, line, values))
+    }
+
+    fn push(&mut self, kind: SyncTexRecordKind, tag: usize, line: u64, rect: PdfRect) {
+        self.synctex.records.push(SyncTexRecord {
+            kind,
+            tag,
+            line,
+            page: self.page,
+            rect,
+        });
+    }
+}
+
+impl SyncTex {
+    pub fn parse(text: &str, base_dir: &Path) -> Self {
+        let mut parser = Parser::default();
+        for line in text.lines() {
+            parser.parse_line(base_dir, line);
+        }
+        parser.synctex
+    }
+
+    pub async fn load(path: &Path, base_dir: &Path) -> Option<Self> {
+        let bytes = fs::read(path).await.ok()?;
+        let text = if path.extension().map_or(false, |ext| ext == "gz") {
+            let mut text = String::new();
+            if let Err(why) = GzDecoder::new(&bytes[..]).read_to_string(&mut text) {
+                warn!("Unable to decompress {}: {}", path.to_string_lossy(), why);
+                return None;
+            }
+            text
+        } else {
+            String::from_utf8_lossy(&bytes).into_owned()
+        };
+        Some(Self::parse(&text, base_dir))
+    }
+
+    pub fn forward(&self, tex_path: &Path, line: u64) -> Option<(u64, PdfRect)> {
+        let tag = self
+            .inputs
+            .iter()
+            .find(|(_, path)| path.as_path() == tex_path)
+            .map(|(tag, _)| *tag)?;
+
+        let candidates: Vec<_> = self
+            .records
+            .iter()
+            .filter(|record| record.tag == tag && record.kind != SyncTexRecordKind::VBox)
+            .collect();
+
+        let best_line = candidates
+            .iter()
+            .map(|record| record.line)
+            .min_by_key(|record_line| {
+                if *record_line >= line {
+                    (0, record_line - line)
+                } else {
+                    (1, line - record_line)
+                }
+            })?;
+
+        let page = candidates
+            .iter()
+            .filter(|record| record.line == best_line)
+            .map(|record| record.page)
+            .min()?;
+
+        let rect = candidates
+            .iter()
+            .filter(|record| record.line == best_line && record.page == page)
+            .map(|record| record.rect)
+            .fold(None, |acc: Option<PdfRect>, rect| {
+                Some(acc.map_or(rect, |acc| acc.union(rect)))
+            })?;
+
+        Some((page, rect))
+    }
+
+    pub fn inverse(&self, page: u64, x: f64, y: f64) -> Option<(&Path, u64)> {
+        let record = self
+            .records
+            .iter()
+            .filter(|record| record.page == page)
+            .map(|record| {
+                let distance = record.rect.distance(x, y);
+                let area = record.rect.width * record.rect.height;
+                (record, distance, area)
+            })
+            .min_by(|(_, d1, a1), (_, d2, a2)| {
+                d1.partial_cmp(d2)
+                    .unwrap()
+                    .then_with(|| a1.partial_cmp(a2).unwrap())
+            })
+            .map(|(record, _, _)| record)?;
+
+        let path = self.inputs.get(&record.tag)?;
+        Some((path, record.line))
+    }
+}
+
+#[derive(Debug)]
+struct CachedSyncTex {
+    modified: SystemTime,
+    base_dir: PathBuf,
+    synctex: Arc<SyncTex>,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncTexCache {
+    files: Mutex<HashMap<PathBuf, CachedSyncTex>>,
+}
+
+impl SyncTexCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn find(&self, pdf_path: &Path, base_dir: &Path) -> Option<Arc<SyncTex>> {
+        for extension in &["synctex.gz", "synctex"] {
+            let path = pdf_path.with_extension(extension);
+            let modified = match fs::metadata(&path).await {
+                Ok(metadata) if metadata.is_file() => metadata.modified().ok()?,
+                _ => continue,
+            };
+
+            let mut files = self.files.lock().await;
+            if let Some(cached) = files.get(&path) {
+                if cached.modified == modified && cached.base_dir == base_dir {
+                    return Some(Arc::clone(&cached.synctex));
+                }
+            }
+
+            let synctex = Arc::new(SyncTex::load(&path, base_dir).await?);
+            files.insert(
+                path,
+                CachedSyncTex {
+                    modified,
+                    base_dir: base_dir.to_owned(),
+                    synctex: Arc::clone(&synctex),
+                },
+            );
+            return Some(synctex);
+        }
+        None
+    }
+}
+
+/// Finds the directory of the compilation that produced the given PDF file.
+/// The paths inside of the SyncTeX file are relative to this directory.
+fn find_build_dir(snapshot: &Snapshot, options: &ScopedOptions, pdf_path: &Path) -> PathBuf {
+    snapshot
+        .documents()
+        .iter()
+        .filter(|doc| doc.uri.scheme() == "file")
+        .filter(|doc| matches!(&doc.content, DocumentContent::Latex(table) if table.is_standalone))
+        .filter_map(|doc| {
+            let tex_path = doc.uri.to_file_path().ok()?;
+            let (options, current_dir) = options.get(&doc.uri);
+            OutputLocations::resolve(&tex_path, options, current_dir)
+        })
+        .find(|locations| locations.paths("pdf").iter().any(|path| path == pdf_path))
+        .map(|locations| locations.build_dir)
+        .or_else(|| pdf_path.parent().map(ToOwned::to_owned))
+        .unwrap_or_default()
+}
+
+pub async fn forward_search<'a>(
+    snapshot: &'a Snapshot,
+    tex_uri: &'a Uri,
+    line_number: u64,
+    options: &Options,
+    current_dir: &'a Path,
+    cache: &SyncTexCache,
+) -> Option<PdfLocation> {
+    let root_uri = &snapshot.parent_subfile(tex_uri)?.uri;
+    if root_uri.scheme() != "file" {
+        return None;
+    }
+
+    let root_path = root_uri.to_file_path().ok()?;
+    let locations = OutputLocations::resolve(&root_path, options, current_dir)?;
+    let pdf_path = locations
+        .paths("pdf")
+        .into_iter()
+        .find(|path| path.exists())?;
+
+    let tex_path = tex_uri.to_file_path().ok()?;
+    let synctex = cache.find(&pdf_path, &locations.build_dir).await?;
+    let (page, rect) = synctex.forward(&tex_path, line_number + 1)?;
+    Some(PdfLocation {
+        uri: Uri::from_file_path(pdf_path).ok()?.into(),
+        page,
+        rect,
+    })
+}
+
+pub async fn inverse_search(
+    snapshot: &Snapshot,
+    options: &ScopedOptions,
+    pdf_uri: &Uri,
+    page: u64,
+    x: f64,
+    y: f64,
+    cache: &SyncTexCache,
+) -> Option<Location> {
+    let pdf_path = pdf_uri.to_file_path().ok()?;
+    let build_dir = find_build_dir(snapshot, options, &pdf_path);
+    let synctex = cache.find(&pdf_path, &build_dir).await?;
+    let (tex_path, line) = synctex.inverse(page, x, y)?;
+    let line = line.saturating_sub(1);
+    Some(Location::new(
+        Uri::from_file_path(tex_path).ok()?.into(),
+        Range::new_simple(line, 0, line, 0),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use tempfile::tempdir;
+
+    const SYNCTEX: &str = indoc!(
+        "
+            SyncTeX Version:1
+            Input:1:/foo/./main.tex
+            Input:2:/foo/chapter.tex
+            Output:pdf
+            Magnification:1000
+            Unit:1
+            X Offset:0
+            Y Offset:0
+            Content:
+            {1
+            [1,3:4736286,4736286:26673152,41152644,0
+            (1,5:4736286,7105429:26673152,655360,0
+            x1,5:4736286,7105429
+            g1,5:9000000,7105429
+            )
+            (2,1:4736286,9000000:26673152,655360,131072
+            k2,1:5000000,9000000:100000
+            )
+            ]
+            }1
+            {2
+            [1,10:4736286,4736286:26673152,41152644,0
+            (1,12:4736286,7105429:13158400,655360,0
+            )
+            ]
+            }2
+            Postamble:
+        "
+    );
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 0.01
+    }
+
+    #[test]
+    fn parse_inputs() {
+        let synctex = SyncTex::parse(SYNCTEX, Path::new("/bar"));
+        assert_eq!(synctex.inputs[&1], PathBuf::from("/foo/main.tex"));
+        assert_eq!(synctex.inputs[&2], PathBuf::from("/foo/chapter.tex"));
+    }
+
+    #[test]
+    fn parse_relative_inputs() {
+        let synctex = SyncTex::parse("Input:1:./src/main.tex", Path::new("/bar"));
+        assert_eq!(synctex.inputs[&1], PathBuf::from("/bar/src/main.tex"));
+    }
+
+    #[test]
+    fn parse_records() {
+        let synctex = SyncTex::parse(SYNCTEX, Path::new("/bar"));
+        assert_eq!(synctex.records.len(), 8);
+
+        let hbox = synctex.records[1];
+        assert_eq!(hbox.kind, SyncTexRecordKind::HBox);
+        assert_eq!((hbox.tag, hbox.line, hbox.page), (1, 5, 1));
+        assert!(approx(hbox.rect.x, 72.0));
+        assert!(approx(hbox.rect.y, 98.05));
+        assert!(approx(hbox.rect.width, 405.48));
+        assert!(approx(hbox.rect.height, 9.96));
+    }
+
+    #[test]
+    fn forward() {
+        let synctex = SyncTex::parse(SYNCTEX, Path::new("/bar"));
+        let (page, rect) = synctex.forward(Path::new("/foo/main.tex"), 5).unwrap();
+        assert_eq!(page, 1);
+        assert!(approx(rect.x, 72.0));
+        assert!(approx(rect.y, 98.05));
+
+        let (page, _) = synctex.forward(Path::new("/foo/main.tex"), 11).unwrap();
+        assert_eq!(page, 2);
+    }
+
+    #[test]
+    fn forward_unknown_file() {
+        let synctex = SyncTex::parse(SYNCTEX, Path::new("/bar"));
+        assert_eq!(synctex.forward(Path::new("/foo/qux.tex"), 1), None);
+    }
+
+    #[test]
+    fn inverse() {
+        let synctex = SyncTex::parse(SYNCTEX, Path::new("/bar"));
+        let (path, line) = synctex.inverse(1, 100.0, 132.0).unwrap();
+        assert_eq!(path, Path::new("/foo/chapter.tex"));
+        assert_eq!(line, 1);
+
+        let (path, line) = synctex.inverse(2, 100.0, 105.0).unwrap();
+        assert_eq!(path, Path::new("/foo/main.tex"));
+        assert_eq!(line, 12);
+    }
+
+    #[tokio::test]
+    async fn cache_unchanged_file() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("main.synctex"), SYNCTEX).unwrap();
+        let cache = SyncTexCache::new();
+        let pdf_path = dir.path().join("main.pdf");
+
+        let first = cache.find(&pdf_path, dir.path()).await.unwrap();
+        let second = cache.find(&pdf_path, dir.path()).await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let other = cache.find(&pdf_path, Path::new("/bar")).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &other));
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
 use itertools::Itertools;
 use petgraph::graph::{Graph, NodeIndex};
 use serde::{Deserialize, Serialize};
-use std::fmt;
+use std::{fmt, string::String as StdString};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum TokenKind {
@@ -394,6 +394,28 @@ impl Tree {
         None
     }
 
+    pub fn field_text(&self, field: NodeIndex) -> Option<StdString> {
+        fn collect_words<'a>(tree: &'a Tree, node: NodeIndex, words: &mut Vec<&'a str>) {
+            for child in tree.children(node) {
+                match &tree.graph[child] {
+                    Node::Word(word) => words.push(word.token.text()),
+                    Node::Command(_) => (),
+                    _ => collect_words(tree, child, words),
+                }
+            }
+        }
+
+        self.as_field(field)?;
+        let mut words = Vec::new();
+        collect_words(self, field, &mut words);
+        let text = words.join(" ").replace(" ,", ",");
+        if text.is_empty() {
+            None
+        } else {
+            Some(text)
+        }
+    }
+
     pub fn crossref(&self, entry: NodeIndex) -> Option<NodeIndex> {
         let field = self.field_by_name(entry, "crossref")?;
         let content = self.children(field).next()?;
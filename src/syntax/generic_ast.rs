@@ -4,7 +4,7 @@ use std::ops::Index;
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub struct AstNodeIndex(usize);
 
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub struct Ast<T> {
     nodes: Vec<T>,
     edges: Vec<Vec<AstNodeIndex>>,
@@ -20,6 +20,9 @@ pub struct LatexCitationCommand {
 pub enum LatexLabelReferenceSource {
     Everything,
     Math,
+    Section,
+    Float,
+    Theorem,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
@@ -99,6 +102,8 @@ pub struct LatexCommandDefinitionCommand {
     pub definition_index: usize,
     pub arg_count_index: usize,
     pub implementation_index: usize,
+    #[serde(default)]
+    pub arg_spec_index: Option<usize>,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -116,6 +121,13 @@ pub struct LatexTheoremDefinitionCommand {
     pub index: usize,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatexEnvironmentDefinitionCommand {
+    pub name: String,
+    pub index: usize,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LatexColorCommand {
@@ -180,6 +192,13 @@ pub struct BibtexFieldDoc {
     pub documentation: String,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BibtexFieldValues {
+    pub fields: Vec<String>,
+    pub values: Vec<String>,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct LanguageData {
@@ -191,6 +210,7 @@ pub struct LanguageData {
     pub command_definition_commands: Vec<LatexCommandDefinitionCommand>,
     pub math_operator_commands: Vec<LatexMathOperatorCommand>,
     pub theorem_definition_commands: Vec<LatexTheoremDefinitionCommand>,
+    pub environment_definition_commands: Vec<LatexEnvironmentDefinitionCommand>,
     pub colors: Vec<String>,
     pub color_commands: Vec<LatexColorCommand>,
     pub color_model_commands: Vec<LatexColorModelCommand>,
@@ -198,13 +218,33 @@ pub struct LanguageData {
     pub glossary_entry_reference_commands: Vec<LatexGlossaryEntryReferenceCommand>,
     pub entry_types: Vec<BibtexEntryTypeDoc>,
     pub fields: Vec<BibtexFieldDoc>,
+    pub field_values: Vec<BibtexFieldValues>,
     pub pgf_libraries: Vec<String>,
     pub tikz_libraries: Vec<String>,
     pub math_environments: Vec<String>,
     pub enum_environments: Vec<String>,
+    pub math_commands: Vec<String>,
+    pub text_commands: Vec<String>,
+    pub text_mode_commands: Vec<String>,
 }
 
 impl LanguageData {
+    pub fn is_math_command(&self, name: &str) -> bool {
+        self.math_commands.iter().any(|cmd| cmd == name)
+    }
+
+    pub fn is_text_mode_command(&self, name: &str) -> bool {
+        self.text_mode_commands.iter().any(|cmd| cmd == name)
+    }
+
+    pub fn is_text_command(&self, name: &str) -> bool {
+        self.text_commands.iter().any(|cmd| cmd == name)
+            || self
+                .section_commands
+                .iter()
+                .any(|cmd| cmd.name[1..].trim_end_matches('*') == name.trim_end_matches('*'))
+    }
+
     pub fn find_entry_type(&self, name: &str) -> Option<&BibtexEntryTypeDoc> {
         let name = name.to_lowercase();
         self.entry_types
@@ -217,6 +257,14 @@ impl LanguageData {
             .and_then(|ty| ty.documentation.as_ref().map(AsRef::as_ref))
     }
 
+    pub fn field_values(&self, name: &str) -> impl Iterator<Item = &str> {
+        let name = name.to_lowercase();
+        self.field_values
+            .iter()
+            .filter(move |values| values.fields.contains(&name))
+            .flat_map(|values| values.values.iter().map(String::as_str))
+    }
+
     pub fn field_documentation(&self, name: &str) -> Option<&str> {
         self.fields
             .iter()
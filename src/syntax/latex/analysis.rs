@@ -5,7 +5,7 @@ use crate::{
 };
 use itertools::{iproduct, Itertools};
 use serde::{Deserialize, Serialize};
-use std::{borrow::Cow, ops::Deref, path::Path};
+use std::{borrow::Cow, iter::Peekable, ops::Deref, path::Path, str::Chars};
 
 #[derive(Debug, Clone)]
 pub struct SymbolTableParams<'a> {
@@ -16,7 +16,7 @@ pub struct SymbolTableParams<'a> {
     pub current_dir: &'a Path,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolTable {
     pub(crate) tree: Tree,
     pub commands: Vec<AstNodeIndex>,
@@ -32,6 +32,7 @@ pub struct SymbolTable {
     pub inlines: Vec<Inline>,
     pub math_operators: Vec<MathOperator>,
     pub theorem_definitions: Vec<TheoremDefinition>,
+    pub environment_definitions: Vec<EnvironmentDefinition>,
     pub sections: Vec<Section>,
     pub labels: Vec<Label>,
     pub label_numberings: Vec<LabelNumbering>,
@@ -69,6 +70,7 @@ impl SymbolTable {
         let mut inlines = None;
         let mut math_operators = None;
         let mut theorem_definitions = None;
+        let mut environment_definitions = None;
         let mut sections = None;
         let mut labels = None;
         let mut label_numberings = None;
@@ -86,6 +88,7 @@ impl SymbolTable {
             s.spawn(|_| inlines = Some(Inline::parse(ctx)));
             s.spawn(|_| math_operators = Some(MathOperator::parse(ctx)));
             s.spawn(|_| theorem_definitions = Some(TheoremDefinition::parse(ctx)));
+            s.spawn(|_| environment_definitions = Some(EnvironmentDefinition::parse(ctx)));
             s.spawn(|_| sections = Some(Section::parse(ctx)));
             s.spawn(|_| labels = Some(Label::parse(ctx)));
             s.spawn(|_| label_numberings = Some(LabelNumbering::parse(ctx)));
@@ -121,6 +124,7 @@ impl SymbolTable {
             inlines: inlines.unwrap(),
             math_operators: math_operators.unwrap(),
             theorem_definitions: theorem_definitions.unwrap(),
+            environment_definitions: environment_definitions.unwrap(),
             sections: sections.unwrap(),
             labels: labels.unwrap(),
             label_numberings: label_numberings.unwrap(),
@@ -170,6 +174,18 @@ impl SymbolTable {
             .filter(|label| label.names(&self.tree).len() == 1)
             .find(|label| self.is_direct_child(env, self.tree[label.parent].start()))
     }
+
+    pub fn find_environment_name(&self, pos: Position) -> Option<&Token> {
+        iproduct!(&self.commands, LANGUAGE_DATA.environment_commands.iter())
+            .filter(|(parent, desc)| {
+                self.tree.as_command(**parent).unwrap().name.text() == desc.name
+            })
+            .filter_map(|(parent, desc)| {
+                self.tree
+                    .extract_word(*parent, GroupKind::Group, desc.index)
+            })
+            .find(|name| name.range().contains(pos))
+    }
 }
 
 impl Deref for SymbolTable {
@@ -470,6 +486,7 @@ pub struct CommandDefinition {
     pub implementation: AstNodeIndex,
     pub implementation_index: usize,
     pub arg_count_index: usize,
+    pub arg_spec_index: Option<usize>,
 }
 
 impl CommandDefinition {
@@ -477,6 +494,39 @@ impl CommandDefinition {
         tree.as_command(self.definition).unwrap().name.text()
     }
 
+    pub fn parameters(self, tree: &Tree) -> Vec<CommandParameter> {
+        match self.arg_spec_index {
+            Some(index) => tree
+                .print_group_content(self.parent, GroupKind::Group, index)
+                .map(|spec| CommandParameter::parse_spec(&spec))
+                .unwrap_or_default(),
+            None => {
+                let count = tree
+                    .extract_word(self.parent, GroupKind::Options, self.arg_count_index)
+                    .and_then(|word| word.text().parse().ok())
+                    .unwrap_or(0);
+
+                let mut default = tree.print_group_content(
+                    self.parent,
+                    GroupKind::Options,
+                    self.arg_count_index + 1,
+                );
+
+                (0..count)
+                    .map(|i| {
+                        if i == 0 && default.is_some() {
+                            CommandParameter::Optional {
+                                default: default.take(),
+                            }
+                        } else {
+                            CommandParameter::Mandatory
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+
     fn parse(ctx: SymbolContext) -> Vec<Self> {
         let def = LANGUAGE_DATA.command_definition_commands.iter();
         iproduct!(ctx.commands, def)
@@ -513,10 +563,91 @@ impl CommandDefinition {
             implementation,
             implementation_index: desc.implementation_index,
             arg_count_index: desc.arg_count_index,
+            arg_spec_index: desc.arg_spec_index,
         })
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub enum CommandParameter {
+    Mandatory,
+    Optional { default: Option<String> },
+}
+
+impl CommandParameter {
+    pub fn parse_spec(spec: &str) -> Vec<Self> {
+        fn skip_group(chars: &mut Peekable<Chars>) -> Option<String> {
+            while chars.peek().map_or(false, |c| c.is_whitespace()) {
+                chars.next();
+            }
+
+            if chars.peek() != Some(&'{') {
+                return None;
+            }
+            chars.next();
+
+            let mut text = String::new();
+            let mut balance = 1;
+            for c in chars {
+                match c {
+                    '{' => balance += 1,
+                    '}' => balance -= 1,
+                    _ => (),
+                }
+
+                if balance == 0 {
+                    break;
+                }
+                text.push(c);
+            }
+            Some(text)
+        }
+
+        let mut params = Vec::new();
+        let mut chars = spec.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                'm' | 'v' => params.push(Self::Mandatory),
+                'r' => {
+                    chars.nth(1);
+                    params.push(Self::Mandatory);
+                }
+                'R' => {
+                    chars.nth(1);
+                    skip_group(&mut chars);
+                    params.push(Self::Mandatory);
+                }
+                'o' => params.push(Self::Optional { default: None }),
+                'd' => {
+                    chars.nth(1);
+                    params.push(Self::Optional { default: None });
+                }
+                'O' => {
+                    let default = skip_group(&mut chars);
+                    params.push(Self::Optional { default });
+                }
+                'D' => {
+                    chars.nth(1);
+                    let default = skip_group(&mut chars);
+                    params.push(Self::Optional { default });
+                }
+                't' => {
+                    chars.next();
+                }
+                'e' => {
+                    skip_group(&mut chars);
+                }
+                'E' => {
+                    skip_group(&mut chars);
+                    skip_group(&mut chars);
+                }
+                _ => (),
+            }
+        }
+        params
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct GlossaryEntry {
     pub parent: AstNodeIndex,
@@ -719,6 +850,45 @@ impl TheoremDefinition {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EnvironmentDefinition {
+    pub parent: AstNodeIndex,
+    pub arg_index: usize,
+}
+
+impl EnvironmentDefinition {
+    pub fn name(self, tree: &Tree) -> &Token {
+        tree.extract_word(self.parent, GroupKind::Group, self.arg_index)
+            .unwrap()
+    }
+
+    fn parse(ctx: SymbolContext) -> Vec<Self> {
+        let def = LANGUAGE_DATA.environment_definition_commands.iter();
+        iproduct!(ctx.commands, def)
+            .filter_map(|(parent, desc)| Self::parse_single(ctx, *parent, desc))
+            .collect()
+    }
+
+    fn parse_single(
+        ctx: SymbolContext,
+        parent: AstNodeIndex,
+        desc: &LatexEnvironmentDefinitionCommand,
+    ) -> Option<Self> {
+        let cmd = ctx.tree.as_command(parent)?;
+        if cmd.name.text() != desc.name {
+            return None;
+        }
+
+        let group_kind = GroupKind::Group;
+        ctx.tree.extract_word(parent, group_kind, desc.index)?;
+
+        Some(Self {
+            parent,
+            arg_index: desc.index,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Section {
     pub parent: AstNodeIndex,
@@ -774,7 +944,28 @@ impl Label {
     }
 
     fn parse(ctx: SymbolContext) -> Vec<Self> {
-        iproduct!(ctx.commands, LANGUAGE_DATA.label_commands.iter())
+        let user_commands: Vec<_> = ctx
+            .options
+            .latex
+            .as_ref()
+            .and_then(|opts| opts.reference_commands.as_ref())
+            .into_iter()
+            .flatten()
+            .map(|(name, source)| LatexLabelCommand {
+                name: format!("\\{}", name.trim_start_matches('\\')),
+                index: 0,
+                kind: LatexLabelKind::Reference(*source),
+            })
+            .filter(|desc| {
+                LANGUAGE_DATA
+                    .label_commands
+                    .iter()
+                    .all(|cmd| cmd.name != desc.name)
+            })
+            .collect();
+
+        let descs = LANGUAGE_DATA.label_commands.iter().chain(&user_commands);
+        iproduct!(ctx.commands, descs)
             .filter_map(|(parent, desc)| Self::parse_single(ctx, *parent, desc))
             .collect()
     }
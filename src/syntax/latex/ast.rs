@@ -155,7 +155,7 @@ impl SyntaxNode for Node {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct Tree {
     pub inner: Ast<Node>,
     pub root: AstNodeIndex,
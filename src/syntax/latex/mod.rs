@@ -21,6 +21,12 @@ pub struct OpenParams<'a> {
     pub current_dir: &'a Path,
 }
 
+pub fn parse(text: &str) -> Tree {
+    let lexer = Lexer::new(text);
+    let parser = Parser::new(lexer);
+    parser.parse()
+}
+
 pub fn open(params: OpenParams) -> SymbolTable {
     let OpenParams {
         text,
@@ -30,12 +36,8 @@ pub fn open(params: OpenParams) -> SymbolTable {
         current_dir,
     } = params;
 
-    let lexer = Lexer::new(text);
-    let parser = Parser::new(lexer);
-    let tree = parser.parse();
-
     let params = SymbolTableParams {
-        tree,
+        tree: parse(text),
         uri,
         resolver,
         options,
@@ -514,6 +516,43 @@ mod tests {
         assert_eq!(actual_cmds, expected_cmds);
     }
 
+    #[test]
+    fn command_definition_parameters() {
+        let table = open_simple(indoc!(
+            r#"
+                \newcommand{\foo}[2][x]{Foo}
+                \NewDocumentCommand{\bar}{s m O{y} D<>{z}}{Bar}
+            "#
+        ));
+
+        let actual_params: Vec<_> = table
+            .command_definitions
+            .iter()
+            .map(|def| def.parameters(&table.tree))
+            .collect();
+
+        assert_eq!(
+            actual_params,
+            vec![
+                vec![
+                    CommandParameter::Optional {
+                        default: Some("x".into())
+                    },
+                    CommandParameter::Mandatory,
+                ],
+                vec![
+                    CommandParameter::Mandatory,
+                    CommandParameter::Optional {
+                        default: Some("y".into())
+                    },
+                    CommandParameter::Optional {
+                        default: Some("z".into())
+                    },
+                ],
+            ]
+        );
+    }
+
     #[test]
     fn glossary_entry() {
         let table = open_simple(indoc!(
@@ -590,6 +629,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn environment_definition() {
+        let table = open_simple(indoc!(
+            r#"
+                \newenvironment{foo}{}{}
+                \NewDocumentEnvironment{bar}{m}{}{}
+                \newtcolorbox[auto counter]{baz}{}
+                \lstnewenvironment{qux}{}{}
+            "#
+        ));
+
+        let actual_names: Vec<_> = table
+            .environment_definitions
+            .iter()
+            .map(|def| def.name(&table.tree).text())
+            .collect();
+
+        assert_eq!(actual_names, vec!["foo", "bar", "baz", "qux"]);
+    }
+
     #[test]
     fn section() {
         let table = open_simple(indoc!(
@@ -24,6 +24,7 @@ pub enum Structure {
     Field,
     Argument,
     GlossaryEntry,
+    KeyValue,
 }
 
 impl Structure {
@@ -56,6 +57,7 @@ impl Structure {
             Self::Field => CompletionItemKind::Field,
             Self::Argument => CompletionItemKind::Value,
             Self::GlossaryEntry => CompletionItemKind::Keyword,
+            Self::KeyValue => CompletionItemKind::Property,
         }
     }
 
@@ -88,6 +90,7 @@ impl Structure {
             Self::Field => SymbolKind::Field,
             Self::Argument => SymbolKind::Number,
             Self::GlossaryEntry => unimplemented!(),
+            Self::KeyValue => SymbolKind::Property,
         }
     }
 }
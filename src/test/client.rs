@@ -25,6 +25,9 @@ pub trait TestLspClient {
     #[jsonrpc_method("workspace/didChangeConfiguration", kind = "notification")]
     async fn did_change_configuration(&self, params: DidChangeConfigurationParams);
 
+    #[jsonrpc_method("workspace/didChangeWatchedFiles", kind = "notification")]
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams);
+
     #[jsonrpc_method("textDocument/definition", kind = "request")]
     async fn definition(&self, params: TextDocumentPositionParams) -> Result<DefinitionResponse>;
 
@@ -68,6 +71,9 @@ pub trait TestLspClient {
     async fn document_symbol(&self, params: DocumentSymbolParams)
         -> Result<DocumentSymbolResponse>;
 
+    #[jsonrpc_method("workspace/executeCommand", kind = "request")]
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<serde_json::Value>;
+
     #[jsonrpc_method("$/detectRoot", kind = "request")]
     async fn detect_root(&self, params: TextDocumentIdentifier) -> Result<()>;
 }
@@ -152,17 +152,14 @@ impl TestBedBuilder {
             DISTRO.get(self.use_distro).await,
             Arc::clone(&client),
             Arc::new(dir.path().to_path_buf()),
+            None,
         ));
 
         Endpoint {
             receiver: rx1,
             client: Arc::clone(&client),
             server: Arc::clone(&server),
-            handler: MessageHandler {
-                client,
-                server,
-                output: tx2.clone(),
-            },
+            handler: MessageHandler::new(server, client, tx2.clone()),
         }
     }
 
@@ -178,6 +175,10 @@ impl TestBedBuilder {
                 build: self.latex_build.clone(),
                 forward_search: self.latex_forward_search.clone(),
                 lint: self.latex_lint.clone(),
+                snippets: None,
+                reference_commands: None,
+                memory_limit_mb: None,
+                indexing: None,
             }),
             bibtex: Some(BibtexOptions {
                 formatting: self.bibtex_formatting.clone(),
@@ -191,11 +192,7 @@ impl TestBedBuilder {
             receiver: rx2,
             client: Arc::clone(&test_client),
             server: Arc::clone(&test_server),
-            handler: MessageHandler {
-                client: test_client,
-                server: test_server,
-                output: tx1,
-            },
+            handler: MessageHandler::new(test_server, test_client, tx1),
         }
     }
 }
@@ -300,6 +297,13 @@ impl TestBed {
         self.client.did_change(params).await;
     }
 
+    pub async fn change_file(&self, relative_path: &str, typ: FileChangeType) {
+        let params = DidChangeWatchedFilesParams {
+            changes: vec![FileEvent::new(self.uri(relative_path).into(), typ)],
+        };
+        self.client.did_change_watched_files(params).await;
+    }
+
     pub async fn push_options(&self) {
         let options = self.server.options.lock().await.clone();
         let params = DidChangeConfigurationParams {
@@ -492,6 +496,19 @@ impl TestBed {
             .unwrap();
     }
 
+    pub async fn execute_command(
+        &self,
+        command: &str,
+        relative_path: &str,
+    ) -> Option<serde_json::Value> {
+        let params = ExecuteCommandParams {
+            command: command.into(),
+            arguments: vec![serde_json::to_value(self.identifier(relative_path)).unwrap()],
+            work_done_progress_params: WorkDoneProgressParams::default(),
+        };
+        self.client.execute_command(params).await.ok()
+    }
+
     pub async fn shutdown(&self) {
         self.client.shutdown(()).await.unwrap();
         self.client.exit(()).await;
@@ -544,6 +561,26 @@ pub static PUSH_CAPABILITIES: ClientCapabilities = {
     }
 };
 
+pub static WATCH_CAPABILITIES: ClientCapabilities = {
+    ClientCapabilities {
+        experimental: None,
+        text_document: None,
+        window: None,
+        workspace: Some(WorkspaceClientCapabilities {
+            apply_edit: None,
+            configuration: Some(true),
+            did_change_configuration: None,
+            did_change_watched_files: Some(GenericCapability {
+                dynamic_registration: Some(true),
+            }),
+            execute_command: None,
+            symbol: None,
+            workspace_edit: None,
+            workspace_folders: None,
+        }),
+    }
+};
+
 pub static NESTED_SYMBOL_CAPABILITIES: ClientCapabilities = {
     ClientCapabilities {
         experimental: None,
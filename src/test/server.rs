@@ -5,6 +5,7 @@ use chashmap::CHashMap;
 use futures::lock::Mutex;
 use jsonrpc::server::{Middleware, Result};
 use jsonrpc_derive::{jsonrpc_method, jsonrpc_server};
+use std::sync::Arc;
 
 pub struct TestLatexLspServer {
     pub options: Mutex<Options>,
@@ -34,11 +35,18 @@ impl TestLatexLspServer {
     #[jsonrpc_method("workspace/configuration", kind = "request")]
     pub async fn configuration(&self, params: ConfigurationParams) -> Result<serde_json::Value> {
         let options = self.options.lock().await;
-        if params.items[0].section.as_ref().unwrap() == "latex" {
-            Ok(serde_json::to_value(vec![options.latex.clone().unwrap_or_default()]).unwrap())
-        } else {
-            Ok(serde_json::to_value(vec![options.bibtex.clone().unwrap_or_default()]).unwrap())
-        }
+        let values: Vec<_> = params
+            .items
+            .iter()
+            .map(|item| {
+                if item.section.as_ref().unwrap() == "latex" {
+                    serde_json::to_value(options.latex.clone().unwrap_or_default()).unwrap()
+                } else {
+                    serde_json::to_value(options.bibtex.clone().unwrap_or_default()).unwrap()
+                }
+            })
+            .collect();
+        Ok(serde_json::to_value(values).unwrap())
     }
 
     #[jsonrpc_method("window/showMessage", kind = "notification")]
@@ -59,6 +67,11 @@ impl TestLatexLspServer {
             .insert(params.uri.into(), params.diagnostics);
     }
 
+    #[jsonrpc_method("workspace/diagnostic/refresh", kind = "request")]
+    pub async fn diagnostic_refresh(&self, _params: ()) -> Result<()> {
+        Ok(())
+    }
+
     #[jsonrpc_method("$/progress", kind = "notification")]
     pub async fn progress(&self, params: ProgressParams) {
         self.progress_buf.push(params);
@@ -83,5 +96,5 @@ impl TestLatexLspServer {
 impl Middleware for TestLatexLspServer {
     async fn before_message(&self) {}
 
-    async fn after_message(&self) {}
+    async fn after_message(self: Arc<Self>) {}
 }
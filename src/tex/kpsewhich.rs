@@ -1,5 +1,7 @@
 use super::Language;
 use futures::Future;
+use log::warn;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     env,
@@ -7,6 +9,7 @@ use std::{
     io,
     path::{Path, PathBuf},
     string::FromUtf8Error,
+    time::SystemTime,
 };
 use thiserror::Error;
 use tokio::{fs, process::Command};
@@ -27,14 +30,18 @@ pub enum KpsewhichError {
     CorruptDatabase,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub struct Resolver {
     pub files_by_name: HashMap<String, PathBuf>,
+    pub distro_version: String,
 }
 
 impl Resolver {
     pub fn new(files_by_name: HashMap<String, PathBuf>) -> Self {
-        Self { files_by_name }
+        Self {
+            files_by_name,
+            distro_version: String::new(),
+        }
     }
 }
 
@@ -65,6 +72,115 @@ where
     Ok(Resolver::new(files_by_name))
 }
 
+const CACHE_VERSION: u32 = 2;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+struct DatabaseStamp {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ResolverCache {
+    version: u32,
+    databases: Vec<DatabaseStamp>,
+    resolver: Resolver,
+}
+
+pub async fn load_database<'a, R, F>(
+    root_directories: &'a [PathBuf],
+    database_path: &str,
+    cache_dir: Option<&Path>,
+    reader: R,
+) -> Result<Resolver, KpsewhichError>
+where
+    R: Fn(&'a Path) -> F,
+    F: Future<Output = Result<Vec<PathBuf>, KpsewhichError>>,
+{
+    let distro_version = run(&["--version"]).await?;
+    let mut databases = Vec::new();
+    for directory in root_directories {
+        let path = directory.join(database_path);
+        let modified = fs::metadata(&path)
+            .await
+            .and_then(|meta| meta.modified())
+            .ok();
+        databases.push(DatabaseStamp { path, modified });
+    }
+
+    let path = cache_dir.map(|dir| dir.join("resolver.json"));
+    if let Some(path) = &path {
+        if let Some(resolver) = load_cache(path, &distro_version, &databases).await {
+            return Ok(resolver);
+        }
+    }
+
+    let mut resolver = parse_database(root_directories, reader).await?;
+    resolver.distro_version = distro_version;
+    if let Some(path) = &path {
+        let cache = ResolverCache {
+            version: CACHE_VERSION,
+            databases,
+            resolver,
+        };
+        save_cache(path, &cache).await;
+        return Ok(cache.resolver);
+    }
+    Ok(resolver)
+}
+
+/// Restores the cached database if the database files of the distribution have not changed.
+/// The distribution version is not checked, so the result needs to be validated
+/// with `load_database` later on.
+pub async fn load_cached_database(cache_dir: Option<&Path>) -> Option<Resolver> {
+    let bytes = fs::read(cache_dir?.join("resolver.json")).await.ok()?;
+    let cache: ResolverCache = serde_json::from_slice(&bytes).ok()?;
+    if cache.version != CACHE_VERSION || cache.databases.is_empty() {
+        return None;
+    }
+
+    for database in &cache.databases {
+        let modified = fs::metadata(&database.path)
+            .await
+            .and_then(|meta| meta.modified())
+            .ok();
+        if modified != database.modified {
+            return None;
+        }
+    }
+    Some(cache.resolver)
+}
+
+async fn load_cache(
+    path: &Path,
+    distro_version: &str,
+    databases: &[DatabaseStamp],
+) -> Option<Resolver> {
+    let bytes = fs::read(path).await.ok()?;
+    let cache: ResolverCache = serde_json::from_slice(&bytes).ok()?;
+    if cache.version == CACHE_VERSION
+        && cache.resolver.distro_version == distro_version
+        && cache.databases == databases
+    {
+        Some(cache.resolver)
+    } else {
+        None
+    }
+}
+
+async fn save_cache(path: &Path, cache: &ResolverCache) {
+    let result = async {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(path, serde_json::to_vec(cache)?).await
+    };
+
+    if let Err(why) = result.await {
+        warn!("Unable to write resolver cache: {}", why);
+    }
+}
+
 fn is_tex_file(path: &Path) -> bool {
     path.extension()
         .and_then(OsStr::to_str)
@@ -110,3 +226,30 @@ where
 
     Ok(result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn load_cached_database_stale() {
+        let dir = tempdir().unwrap();
+        let database = dir.path().join("ls-R");
+        std::fs::write(&database, "").unwrap();
+        let modified = std::fs::metadata(&database).unwrap().modified().ok();
+        let cache = ResolverCache {
+            version: CACHE_VERSION,
+            databases: vec![DatabaseStamp {
+                path: database.clone(),
+                modified,
+            }],
+            resolver: Resolver::default(),
+        };
+        save_cache(&dir.path().join("resolver.json"), &cache).await;
+
+        assert!(load_cached_database(Some(dir.path())).await.is_some());
+        std::fs::remove_file(&database).unwrap();
+        assert!(load_cached_database(Some(dir.path())).await.is_none());
+    }
+}
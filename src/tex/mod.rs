@@ -11,7 +11,7 @@ pub use self::{
 
 use self::{compile::Compiler, miktex::Miktex, tectonic::Tectonic, texlive::Texlive};
 use async_trait::async_trait;
-use std::{fmt, process::Stdio, sync::Arc};
+use std::{fmt, path::Path, process::Stdio, sync::Arc};
 use tokio::process::Command;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -93,7 +93,10 @@ pub trait Distribution: Send + Sync {
 
     async fn compile<'a>(&'a self, params: CompileParams<'a>) -> Result<Artifacts, CompileError>;
 
-    async fn load(&self) -> Result<(), KpsewhichError>;
+    /// Restores the file database of the last session without querying the distribution.
+    async fn load_cached(&self, cache_dir: Option<&Path>) -> bool;
+
+    async fn load(&self, cache_dir: Option<&Path>) -> Result<(), KpsewhichError>;
 
     async fn resolver(&self) -> Arc<Resolver>;
 }
@@ -144,7 +147,11 @@ impl Distribution for UnknownDistribution {
         Err(CompileError::NotInstalled)
     }
 
-    async fn load(&self) -> Result<(), KpsewhichError> {
+    async fn load_cached(&self, _cache_dir: Option<&Path>) -> bool {
+        false
+    }
+
+    async fn load(&self, _cache_dir: Option<&Path>) -> Result<(), KpsewhichError> {
         Ok(())
     }
 
@@ -4,7 +4,7 @@ use super::{
     Distribution, DistributionKind,
 };
 use async_trait::async_trait;
-use std::sync::Arc;
+use std::{path::Path, sync::Arc};
 
 #[derive(Debug, Default)]
 pub struct Tectonic;
@@ -26,7 +26,11 @@ impl Distribution for Tectonic {
         compiler.compile(params.code).await
     }
 
-    async fn load(&self) -> Result<(), KpsewhichError> {
+    async fn load_cached(&self, _cache_dir: Option<&Path>) -> bool {
+        false
+    }
+
+    async fn load(&self, _cache_dir: Option<&Path>) -> Result<(), KpsewhichError> {
         Ok(())
     }
 
@@ -28,9 +28,21 @@ impl Distribution for Texlive {
         compile(params).await
     }
 
-    async fn load(&self) -> Result<(), KpsewhichError> {
+    async fn load_cached(&self, cache_dir: Option<&Path>) -> bool {
+        match kpsewhich::load_cached_database(cache_dir).await {
+            Some(resolver) => {
+                *self.resolver.lock().await = Arc::new(resolver);
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn load(&self, cache_dir: Option<&Path>) -> Result<(), KpsewhichError> {
         let root_directories = kpsewhich::root_directories().await?;
-        let resolver = kpsewhich::parse_database(&root_directories, read_database).await?;
+        let resolver =
+            kpsewhich::load_database(&root_directories, DATABASE_PATH, cache_dir, read_database)
+                .await?;
         mem::replace(&mut *self.resolver.lock().await, Arc::new(resolver));
         Ok(())
     }
@@ -1,14 +1,17 @@
 use crate::{
     components::COMPONENT_DATABASE,
-    protocol::{Options, TextDocumentItem, Uri},
+    document_cache::{CacheKey, DocumentCache},
+    output::OutputLocations,
+    protocol::{LatexIndexingOptions, LatexOptions, Options, ScopedOptions, TextDocumentItem, Uri},
     syntax::{bibtex, latex, LatexIncludeKind},
     tex::{Distribution, Language, Resolver},
 };
-use futures::lock::Mutex;
+use futures::{channel::oneshot, future, lock::Mutex};
+use ignore::{overrides::OverrideBuilder, DirEntry, WalkBuilder};
 use log::{debug, error, warn};
-use petgraph::{graph::Graph, visit::Dfs};
+use rayon::prelude::*;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     ffi::OsStr,
     hash::{Hash, Hasher},
     io,
@@ -59,6 +62,7 @@ pub struct Document {
     pub text: String,
     pub content: DocumentContent,
     pub modified: SystemTime,
+    pub version: Option<i64>,
 }
 
 impl Document {
@@ -98,6 +102,7 @@ impl Document {
             text,
             content,
             modified: SystemTime::now(),
+            version: None,
         }
     }
 }
@@ -117,82 +122,257 @@ impl Hash for Document {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
-pub struct Snapshot(pub Vec<Arc<Document>>);
+pub struct DependencyGraph {
+    targets_by_uri: HashMap<Uri, Vec<Vec<Uri>>>,
+    children_by_uri: HashMap<Uri, Vec<Uri>>,
+    parents_by_uri: HashMap<Uri, Vec<Uri>>,
+    dependents_by_target: HashMap<Uri, HashSet<Uri>>,
+}
+
+impl DependencyGraph {
+    pub fn children(&self, uri: &Uri) -> &[Uri] {
+        self.children_by_uri.get(uri).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn parents(&self, uri: &Uri) -> &[Uri] {
+        self.parents_by_uri.get(uri).map_or(&[], Vec::as_slice)
+    }
+
+    fn update<F>(&mut self, uri: &Uri, targets: Vec<Vec<Uri>>, exists: F)
+    where
+        F: Fn(&Uri) -> bool,
+    {
+        self.detach(uri);
+        for target in targets.iter().flatten() {
+            self.dependents_by_target
+                .entry(target.clone())
+                .or_default()
+                .insert(uri.clone());
+        }
+        self.targets_by_uri.insert(uri.clone(), targets);
+        self.resolve(uri, &exists);
+        self.resolve_dependents(uri, &exists);
+    }
+
+    fn remove<F>(&mut self, uri: &Uri, exists: F)
+    where
+        F: Fn(&Uri) -> bool,
+    {
+        self.detach(uri);
+        self.resolve_dependents(uri, exists);
+    }
+
+    fn detach(&mut self, uri: &Uri) {
+        if let Some(targets) = self.targets_by_uri.remove(uri) {
+            for target in targets.iter().flatten() {
+                if let Some(dependents) = self.dependents_by_target.get_mut(target) {
+                    dependents.remove(uri);
+                }
+            }
+        }
+        self.unlink(uri);
+    }
+
+    fn unlink(&mut self, uri: &Uri) {
+        for child in self.children_by_uri.remove(uri).unwrap_or_default() {
+            if let Some(parents) = self.parents_by_uri.get_mut(&child) {
+                parents.retain(|parent| parent != uri);
+            }
+        }
+    }
+
+    fn resolve<F>(&mut self, uri: &Uri, exists: F)
+    where
+        F: Fn(&Uri) -> bool,
+    {
+        self.unlink(uri);
+
+        let mut children = Vec::new();
+        for targets in self.targets_by_uri.get(uri).into_iter().flatten() {
+            if let Some(child) = targets.iter().find(|target| exists(target)) {
+                if !children.contains(child) {
+                    children.push(child.clone());
+                }
+            }
+        }
+
+        for child in &children {
+            self.parents_by_uri
+                .entry(child.clone())
+                .or_default()
+                .push(uri.clone());
+        }
+        self.children_by_uri.insert(uri.clone(), children);
+    }
+
+    fn resolve_dependents<F>(&mut self, uri: &Uri, exists: F)
+    where
+        F: Fn(&Uri) -> bool,
+    {
+        let dependents: Vec<_> = self
+            .dependents_by_target
+            .get(uri)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect();
+
+        for dependent in dependents {
+            self.resolve(&dependent, &exists);
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct Snapshot {
+    documents: Vec<Arc<Document>>,
+    indices_by_uri: HashMap<Uri, usize>,
+    graph: DependencyGraph,
+    open_uris: HashSet<Uri>,
+}
 
 impl Snapshot {
     pub fn new() -> Self {
-        Self(Vec::new())
+        Self::default()
     }
 
-    pub fn push(&mut self, doc: Document) {
-        self.0.push(Arc::new(doc));
+    pub fn documents(&self) -> &[Arc<Document>] {
+        &self.documents
     }
 
-    pub fn find(&self, uri: &Uri) -> Option<Arc<Document>> {
-        self.0.iter().find(|doc| doc.uri == *uri).map(Arc::clone)
+    pub fn graph(&self) -> &DependencyGraph {
+        &self.graph
     }
 
-    pub fn relations(
-        &self,
-        uri: &Uri,
-        options: &Options,
-        current_dir: &Path,
-    ) -> Vec<Arc<Document>> {
-        let mut graph = Graph::new_undirected();
-        let mut indices_by_uri = HashMap::new();
-        for document in &self.0 {
-            indices_by_uri.insert(&document.uri, graph.add_node(document));
+    pub fn is_open(&self, uri: &Uri) -> bool {
+        self.open_uris.contains(uri)
+    }
+
+    pub fn set_open(&mut self, uri: &Uri, open: bool) {
+        if open {
+            self.open_uris.insert(uri.clone());
+        } else {
+            self.open_uris.remove(uri);
         }
+    }
 
-        for parent in &self.0 {
-            if let DocumentContent::Latex(table) = &parent.content {
-                table
-                    .includes
-                    .iter()
-                    .flat_map(|include| include.all_targets.iter())
-                    .filter_map(|targets| targets.iter().find_map(|target| self.find(target)))
-                    .for_each(|child| {
-                        graph.add_edge(indices_by_uri[&parent.uri], indices_by_uri[&child.uri], ());
-                    });
+    pub fn orphans(&self) -> Vec<Uri> {
+        let mut reachable = HashSet::new();
+        for uri in &self.open_uris {
+            if !reachable.contains(uri) {
+                reachable.extend(self.relations(uri).into_iter().map(|doc| doc.uri.clone()));
+            }
+        }
 
-                table
-                    .imports
-                    .iter()
-                    .flat_map(|import| import.targets.iter())
-                    .find_map(|target| self.find(target))
-                    .into_iter()
-                    .for_each(|child| {
-                        graph.add_edge(indices_by_uri[&parent.uri], indices_by_uri[&child.uri], ());
-                    });
+        self.documents
+            .iter()
+            .map(|doc| &doc.uri)
+            .filter(|uri| !reachable.contains(*uri))
+            .cloned()
+            .collect()
+    }
 
-                self.resolve_aux_targets(&parent.uri, options, current_dir, "aux")
-                    .into_iter()
-                    .flatten()
-                    .find_map(|target| self.find(&target))
-                    .into_iter()
-                    .for_each(|child| {
-                        graph.add_edge(indices_by_uri[&parent.uri], indices_by_uri[&child.uri], ());
-                    });
+    pub fn insert(&mut self, doc: Arc<Document>, options: &Options, current_dir: &Path) {
+        let uri = doc.uri.clone();
+        let targets = self.targets(&doc, options, current_dir);
+        match self.indices_by_uri.get(&uri) {
+            Some(index) => self.documents[*index] = doc,
+            None => {
+                self.indices_by_uri
+                    .insert(uri.clone(), self.documents.len());
+                self.documents.push(doc);
             }
         }
 
+        let indices_by_uri = &self.indices_by_uri;
+        self.graph
+            .update(&uri, targets, |target| indices_by_uri.contains_key(target));
+    }
+
+    pub fn remove(&mut self, uri: &Uri) -> Option<Arc<Document>> {
+        let index = self.indices_by_uri.remove(uri)?;
+        let doc = self.documents.remove(index);
+        for doc in &self.documents[index..] {
+            *self.indices_by_uri.get_mut(&doc.uri).unwrap() -= 1;
+        }
+
+        self.open_uris.remove(uri);
+        let indices_by_uri = &self.indices_by_uri;
+        self.graph
+            .remove(uri, |target| indices_by_uri.contains_key(target));
+        Some(doc)
+    }
+
+    pub fn find(&self, uri: &Uri) -> Option<Arc<Document>> {
+        self.indices_by_uri
+            .get(uri)
+            .map(|index| Arc::clone(&self.documents[*index]))
+    }
+
+    pub fn relations(&self, uri: &Uri) -> Vec<Arc<Document>> {
         let mut documents = Vec::new();
-        if self.find(uri).is_some() {
-            let mut dfs = Dfs::new(&graph, indices_by_uri[uri]);
-            while let Some(index) = dfs.next(&graph) {
-                documents.push(Arc::clone(&graph[index]));
+        if self.find(uri).is_none() {
+            return documents;
+        }
+
+        let mut visited = HashSet::new();
+        let mut stack = vec![uri.clone()];
+        while let Some(uri) = stack.pop() {
+            if !visited.insert(uri.clone()) {
+                continue;
             }
+
+            let neighbors = self.neighbors(&uri);
+            stack.extend(
+                neighbors
+                    .into_iter()
+                    .rev()
+                    .filter(|neighbor| !visited.contains(*neighbor))
+                    .cloned(),
+            );
+            documents.push(self.find(&uri).unwrap());
         }
         documents
     }
 
-    pub fn parent(
-        &self,
-        uri: &Uri,
-        options: &Options,
-        current_dir: &Path,
-    ) -> Option<Arc<Document>> {
-        for doc in self.relations(uri, options, current_dir) {
+    fn neighbors(&self, uri: &Uri) -> Vec<&Uri> {
+        let index = self.indices_by_uri[uri];
+        let mut parents: Vec<_> = self
+            .graph
+            .parents(uri)
+            .iter()
+            .map(|parent| (self.indices_by_uri[parent], parent))
+            .filter(|(i, _)| *i != index)
+            .collect();
+        parents.sort_by_key(|(i, _)| *i);
+
+        let (before, after): (Vec<_>, Vec<_>) = parents.into_iter().partition(|(i, _)| *i < index);
+        before
+            .into_iter()
+            .map(|(_, parent)| parent)
+            .chain(self.graph.children(uri))
+            .chain(after.into_iter().map(|(_, parent)| parent))
+            .collect()
+    }
+
+    fn targets(&self, doc: &Document, options: &Options, current_dir: &Path) -> Vec<Vec<Uri>> {
+        let mut targets = Vec::new();
+        if let DocumentContent::Latex(table) = &doc.content {
+            for include in &table.includes {
+                targets.extend(include.all_targets.iter().cloned());
+            }
+
+            for import in &table.imports {
+                targets.push(import.targets.clone());
+            }
+
+            targets.extend(self.resolve_aux_targets(&doc.uri, options, current_dir, "aux"));
+        }
+        targets
+    }
+
+    pub fn parent(&self, uri: &Uri) -> Option<Arc<Document>> {
+        for doc in self.relations(uri) {
             if let DocumentContent::Latex(table) = &doc.content {
                 if table.is_standalone {
                     return Some(doc);
@@ -202,13 +382,8 @@ impl Snapshot {
         None
     }
 
-    pub fn parent_subfile(
-        &self,
-        uri: &Uri,
-        options: &Options,
-        current_dir: &Path,
-    ) -> Option<Arc<Document>> {
-        for doc in self.relations(uri, options, current_dir) {
+    pub fn parent_subfile(&self, uri: &Uri) -> Option<Arc<Document>> {
+        for doc in self.relations(uri) {
             if let DocumentContent::Latex(table) = &doc.content {
                 if table.is_standalone
                     && !table.components.iter().any(|comp| comp == "subfiles.cls")
@@ -220,10 +395,11 @@ impl Snapshot {
         None
     }
 
-    pub fn expand(&self, options: &Options, current_dir: &Path) -> Vec<Uri> {
+    pub fn expand(&self, options: &ScopedOptions) -> Vec<Uri> {
         let mut unknown_targets = Vec::new();
-        for parent in &self.0 {
+        for parent in &self.documents {
             if let DocumentContent::Latex(table) = &parent.content {
+                let (options, current_dir) = options.get(&parent.uri);
                 table
                     .includes
                     .iter()
@@ -277,35 +453,26 @@ impl Snapshot {
         extension: &str,
     ) -> Option<Vec<Uri>> {
         let mut targets = Vec::new();
-        targets.push(tex_uri.with_extension(extension)?);
         if tex_uri.scheme() == "file" {
             let tex_path = tex_uri.to_file_path().ok()?;
-            let file_stem = tex_path.file_stem()?;
-            let aux_name = format!("{}.{}", file_stem.to_str()?, extension);
-
-            if let Some(root_dir) = options
-                .latex
-                .as_ref()
-                .and_then(|opts| opts.root_directory.as_ref())
-            {
-                let path = current_dir.join(root_dir).join(&aux_name);
-                targets.push(Uri::from_file_path(path).ok()?);
-            }
-
-            if let Some(build_dir) = options
-                .latex
-                .as_ref()
-                .and_then(|opts| opts.build.as_ref())
-                .and_then(|opts| opts.output_directory.as_ref())
-            {
-                let path = current_dir.join(build_dir).join(&aux_name);
+            let locations = OutputLocations::resolve(&tex_path, options, current_dir)?;
+            for path in locations.paths(extension) {
                 targets.push(Uri::from_file_path(path).ok()?);
             }
+        } else {
+            targets.push(tex_uri.with_extension(extension)?);
         }
         Some(targets)
     }
 }
 
+struct LoadedFile {
+    uri: Uri,
+    language: Language,
+    text: String,
+    modified: Option<SystemTime>,
+}
+
 #[derive(Debug, Error)]
 pub enum WorkspaceLoadError {
     #[error("invalid language id")]
@@ -314,20 +481,22 @@ pub enum WorkspaceLoadError {
     InvalidPath,
     #[error("an I/O error occurred: `{0}`")]
     IO(#[from] io::Error),
+    #[error("the memory limit has been exceeded")]
+    MemoryLimit,
 }
 
 pub struct Workspace {
     distro: Arc<dyn Distribution>,
-    current_dir: Arc<PathBuf>,
     snapshot: Mutex<Arc<Snapshot>>,
+    cache: DocumentCache,
 }
 
 impl Workspace {
-    pub fn new(distro: Arc<dyn Distribution>, current_dir: Arc<PathBuf>) -> Self {
+    pub fn new(distro: Arc<dyn Distribution>, cache_path: Option<PathBuf>) -> Self {
         Self {
             distro,
-            current_dir,
             snapshot: Mutex::default(),
+            cache: DocumentCache::new(cache_path),
         }
     }
 
@@ -336,7 +505,7 @@ impl Workspace {
         Arc::clone(&snapshot)
     }
 
-    pub async fn add(&self, document: TextDocumentItem, options: &Options) {
+    pub async fn add(&self, document: TextDocumentItem, options: &ScopedOptions) {
         let language = match Language::by_language_id(&document.language_id) {
             Some(language) => language,
             None => {
@@ -349,19 +518,210 @@ impl Workspace {
         };
 
         debug!("Adding document: {}", document.uri);
+        let uri: Uri = document.uri.into();
         let mut snapshot = self.snapshot.lock().await;
-        *snapshot = self
+        let mut new_snapshot = self
             .add_or_update(
                 &snapshot,
-                document.uri.into(),
+                uri.clone(),
                 document.text,
                 language,
                 options,
+                Some(document.version),
             )
             .await;
+        Arc::make_mut(&mut new_snapshot).set_open(&uri, true);
+        *snapshot = new_snapshot;
+    }
+
+    pub async fn close(&self, uri: &Uri) {
+        debug!("Closing document: {}", uri);
+        let mut snapshot = self.snapshot.lock().await;
+        if snapshot.is_open(uri) {
+            Arc::make_mut(&mut snapshot).set_open(uri, false);
+        }
+    }
+
+    pub async fn remove(&self, uri: &Uri) -> bool {
+        let mut snapshot = self.snapshot.lock().await;
+        if snapshot.find(uri).is_none() || snapshot.is_open(uri) {
+            return false;
+        }
+
+        debug!("Unloading document: {}", uri);
+        Arc::make_mut(&mut snapshot).remove(uri);
+        true
+    }
+
+    pub async fn collect_garbage(&self, options: &ScopedOptions) -> Vec<Uri> {
+        let mut snapshot = self.snapshot.lock().await;
+        let mut unused_uris: Vec<_> = snapshot
+            .orphans()
+            .into_iter()
+            .filter(|uri| !Self::is_indexed(uri, options))
+            .collect();
+        for doc in snapshot.documents() {
+            if doc.is_file() && !snapshot.is_open(&doc.uri) && !unused_uris.contains(&doc.uri) {
+                if let Ok(path) = doc.uri.to_file_path() {
+                    if !path.exists() {
+                        unused_uris.push(doc.uri.clone());
+                    }
+                }
+            }
+        }
+
+        if !unused_uris.is_empty() {
+            let snapshot = Arc::make_mut(&mut snapshot);
+            for uri in &unused_uris {
+                debug!("Unloading document: {}", uri);
+                snapshot.remove(uri);
+            }
+        }
+
+        let mut orphans: Vec<_> = snapshot
+            .orphans()
+            .into_iter()
+            .filter_map(|uri| snapshot.find(&uri))
+            .collect();
+        orphans.sort_by_key(|doc| doc.text.len());
+        while let Some(doc) = orphans.pop() {
+            if let Some((size, limit)) = Self::memory_usage(&snapshot, options, &doc.uri) {
+                if size > limit {
+                    debug!("Memory limit exceeded, unloading document: {}", doc.uri);
+                    Arc::make_mut(&mut snapshot).remove(&doc.uri);
+                    unused_uris.push(doc.uri.clone());
+                }
+            }
+        }
+        unused_uris
+    }
+
+    /// Returns the size of the loaded documents in the folder of the given document
+    /// together with the memory limit of this folder.
+    fn memory_usage(
+        snapshot: &Snapshot,
+        options: &ScopedOptions,
+        uri: &Uri,
+    ) -> Option<(usize, usize)> {
+        let limit = options
+            .get(uri)
+            .0
+            .latex
+            .as_ref()
+            .and_then(LatexOptions::memory_limit)?;
+
+        let folder = options.folder(uri).map(|folder| &folder.path);
+        let size = snapshot
+            .documents()
+            .iter()
+            .filter(|doc| options.folder(&doc.uri).map(|folder| &folder.path) == folder)
+            .map(|doc| doc.text.len())
+            .sum();
+        Some((size, limit))
+    }
+
+    pub fn is_indexed(uri: &Uri, options: &ScopedOptions) -> bool {
+        options
+            .folder(uri)
+            .and_then(|folder| options.folder_options(folder).latex.as_ref())
+            .and_then(|opts| opts.indexing.as_ref())
+            .map_or(false, LatexIndexingOptions::on_startup)
+    }
+
+    pub fn find_files(root: &Path, exclude: &[String]) -> Vec<PathBuf> {
+        let mut overrides = OverrideBuilder::new(root);
+        for pattern in exclude {
+            if let Err(why) = overrides.add(&format!("!{}", pattern)) {
+                warn!("Invalid exclude pattern {}: {}", pattern, why);
+            }
+        }
+
+        let mut walker = WalkBuilder::new(root);
+        walker.require_git(false);
+        match overrides.build() {
+            Ok(overrides) => {
+                walker.overrides(overrides);
+            }
+            Err(why) => warn!("Invalid exclude patterns: {}", why),
+        }
+
+        walker
+            .build()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().map_or(false, |ty| ty.is_file()))
+            .map(DirEntry::into_path)
+            .filter(|path| {
+                path.extension()
+                    .and_then(OsStr::to_str)
+                    .map_or(false, |ext| ext == "tex" || ext == "bib")
+            })
+            .collect()
+    }
+
+    pub async fn load(
+        &self,
+        path: &Path,
+        options: &ScopedOptions,
+    ) -> Result<(), WorkspaceLoadError> {
+        let file = Self::read(path).await?;
+        let resolver = self.distro.resolver().await;
+        let key = Self::cache_key(&file, &resolver, options);
+        let document = match self.open_cached(file, key).await {
+            Ok(document) => document,
+            Err(file) => {
+                let document = Self::open_file(file, &resolver, options);
+                self.cache_document(&document, key).await;
+                document
+            }
+        };
+
+        let mut snapshot = self.snapshot.lock().await;
+        let mut new_snapshot = Snapshot::clone(&snapshot);
+        Self::insert_document(&mut new_snapshot, document, options)?;
+        *snapshot = Arc::new(new_snapshot);
+        Ok(())
     }
 
-    pub async fn load(&self, path: &Path, options: &Options) -> Result<(), WorkspaceLoadError> {
+    /// Reads and parses the given files in the background and adds them to the workspace at once.
+    pub async fn load_all(&self, paths: &[PathBuf], options: &ScopedOptions) {
+        let files = future::join_all(paths.iter().map(|path| Self::read(path))).await;
+        let resolver = self.distro.resolver().await;
+        let mut documents = Vec::new();
+        let mut pending = Vec::new();
+        for file in files.into_iter().filter_map(Result::ok) {
+            let key = Self::cache_key(&file, &resolver, options);
+            match self.open_cached(file, key).await {
+                Ok(document) => documents.push(document),
+                Err(file) => pending.push((file, key)),
+            }
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let scoped_options = options.clone();
+        rayon::spawn(move || {
+            let documents: Vec<_> = pending
+                .into_par_iter()
+                .map(|(file, key)| (Self::open_file(file, &resolver, &scoped_options), key))
+                .collect();
+            let _ = sender.send(documents);
+        });
+
+        for (document, key) in receiver.await.unwrap_or_default() {
+            self.cache_document(&document, key).await;
+            documents.push(document);
+        }
+
+        let mut snapshot = self.snapshot.lock().await;
+        let mut new_snapshot = Snapshot::clone(&snapshot);
+        for document in documents {
+            if !new_snapshot.is_open(&document.uri) {
+                let _ = Self::insert_document(&mut new_snapshot, document, options);
+            }
+        }
+        *snapshot = Arc::new(new_snapshot);
+    }
+
+    async fn read(path: &Path) -> Result<LoadedFile, WorkspaceLoadError> {
         let language = match path
             .extension()
             .and_then(OsStr::to_str)
@@ -390,18 +750,107 @@ impl Workspace {
             }
         };
 
-        debug!("Loading document: {}", uri);
-        let mut snapshot = self.snapshot.lock().await;
-        *snapshot = self
-            .add_or_update(&snapshot, uri, text, language, options)
-            .await;
+        let modified = fs::metadata(path)
+            .await
+            .and_then(|meta| meta.modified())
+            .ok();
+
+        Ok(LoadedFile {
+            uri,
+            language,
+            text,
+            modified,
+        })
+    }
+
+    fn cache_key(
+        file: &LoadedFile,
+        resolver: &Resolver,
+        options: &ScopedOptions,
+    ) -> Option<CacheKey> {
+        let (options, current_dir) = options.get(&file.uri);
+        match (file.language, file.modified) {
+            (Language::Latex, Some(modified)) => Some(CacheKey::new(
+                modified,
+                &file.text,
+                resolver,
+                options,
+                current_dir,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Restores the analysis of the file from the document cache.
+    /// The file is handed back if it needs to be parsed.
+    async fn open_cached(
+        &self,
+        file: LoadedFile,
+        key: Option<CacheKey>,
+    ) -> Result<Document, LoadedFile> {
+        let table = match key {
+            Some(key) => self.cache.get(&file.uri, key).await,
+            None => None,
+        };
+
+        match table {
+            Some(table) => Ok(Document {
+                uri: file.uri,
+                text: file.text,
+                content: DocumentContent::Latex(Box::new(table)),
+                modified: SystemTime::now(),
+                version: None,
+            }),
+            None => Err(file),
+        }
+    }
+
+    fn open_file(file: LoadedFile, resolver: &Resolver, options: &ScopedOptions) -> Document {
+        let (options, current_dir) = options.get(&file.uri);
+        Document::open(DocumentParams {
+            uri: file.uri,
+            text: file.text,
+            language: file.language,
+            resolver,
+            options,
+            current_dir,
+        })
+    }
+
+    async fn cache_document(&self, document: &Document, key: Option<CacheKey>) {
+        if let (Some(key), DocumentContent::Latex(table)) = (key, &document.content) {
+            self.cache.insert(document.uri.clone(), key, table).await;
+        }
+    }
+
+    fn insert_document(
+        snapshot: &mut Snapshot,
+        document: Document,
+        options: &ScopedOptions,
+    ) -> Result<(), WorkspaceLoadError> {
+        if let Some((size, limit)) = Self::memory_usage(snapshot, options, &document.uri) {
+            if snapshot.find(&document.uri).is_none() && size + document.text.len() > limit {
+                warn!("Memory limit exceeded, skipping document: {}", document.uri);
+                return Err(WorkspaceLoadError::MemoryLimit);
+            }
+        }
+
+        debug!("Loading document: {}", document.uri);
+        let (options, current_dir) = options.get(&document.uri);
+        snapshot.insert(Arc::new(document), options, current_dir);
         Ok(())
     }
 
-    pub async fn update(&self, uri: Uri, text: String, options: &Options) {
+    pub async fn update(
+        &self,
+        uri: Uri,
+        version: Option<i64>,
+        text: String,
+        options: &ScopedOptions,
+    ) {
         let mut snapshot = self.snapshot.lock().await;
 
-        let old_document = match snapshot.0.iter().find(|x| x.uri == uri) {
+        let old_document = match snapshot.find(&uri) {
             Some(document) => document,
             None => {
                 warn!("Document not found: {}", uri);
@@ -409,19 +858,26 @@ impl Workspace {
             }
         };
 
+        if let (Some(old_version), Some(new_version)) = (old_document.version, version) {
+            if new_version < old_version {
+                warn!("Discarding stale update of document: {}", uri);
+                return;
+            }
+        }
+
         let language = match old_document.content {
             DocumentContent::Latex(_) => Language::Latex,
             DocumentContent::Bibtex(_) => Language::Bibtex,
         };
 
         *snapshot = self
-            .add_or_update(&snapshot, uri, text, language, options)
+            .add_or_update(&snapshot, uri, text, language, options, version)
             .await;
     }
 
-    pub async fn reparse(&self, options: &Options) {
+    pub async fn reparse(&self, options: &ScopedOptions) {
         let snapshot = self.get().await;
-        for doc in &snapshot.0 {
+        for doc in snapshot.documents() {
             let language = match doc.content {
                 DocumentContent::Latex(_) => Language::Latex,
                 DocumentContent::Bibtex(_) => Language::Bibtex,
@@ -436,12 +892,13 @@ impl Workspace {
                     doc.text.clone(),
                     language,
                     options,
+                    doc.version,
                 )
                 .await;
         }
     }
 
-    pub async fn detect_root(&self, uri: &Uri, options: &Options) -> io::Result<()> {
+    pub async fn detect_root(&self, uri: &Uri, options: &ScopedOptions) -> io::Result<()> {
         if uri.scheme() != "file" {
             return Ok(());
         }
@@ -449,10 +906,7 @@ impl Workspace {
         if let Ok(mut path) = uri.to_file_path() {
             while path.pop() {
                 let snapshot = self.get().await;
-                if snapshot
-                    .parent_subfile(&uri, &options, &self.current_dir)
-                    .is_some()
-                {
+                if snapshot.parent_subfile(&uri).is_some() {
                     break;
                 }
 
@@ -479,13 +933,13 @@ impl Workspace {
         Ok(())
     }
 
-    pub async fn detect_children(&self, options: &Options) {
+    pub async fn detect_children(&self, options: &ScopedOptions) {
         loop {
             let mut changed = false;
 
             let snapshot = self.get().await;
             for path in snapshot
-                .expand(&options, &self.current_dir)
+                .expand(&options)
                 .into_iter()
                 .filter(|uri| uri.scheme() == "file" && uri.fragment().is_none())
                 .filter_map(|uri| uri.to_file_path().ok())
@@ -501,9 +955,16 @@ impl Workspace {
         }
     }
 
-    pub async fn reparse_all_if_newer(&self, options: &Options) {
+    pub async fn reparse_all_if_newer<F>(&self, options: &ScopedOptions, predicate: F)
+    where
+        F: Fn(&Uri) -> bool,
+    {
         let snapshot = self.get().await;
-        for doc in &snapshot.0 {
+        for doc in snapshot
+            .documents()
+            .iter()
+            .filter(|doc| !snapshot.is_open(&doc.uri) && predicate(&doc.uri))
+        {
             if let Err(WorkspaceLoadError::IO(why)) = self.reparse_if_newer(doc, options).await {
                 warn!("Reparsing document {} failed: {}", doc.uri, why);
             }
@@ -513,7 +974,7 @@ impl Workspace {
     async fn reparse_if_newer(
         &self,
         doc: &Document,
-        options: &Options,
+        options: &ScopedOptions,
     ) -> Result<(), WorkspaceLoadError> {
         if !doc.is_file() {
             return Ok(());
@@ -528,42 +989,59 @@ impl Workspace {
         Ok(())
     }
 
+    pub async fn prune_cache(&self) {
+        self.cache.prune().await;
+    }
+
     async fn add_or_update(
         &self,
         snapshot: &Snapshot,
         uri: Uri,
         text: String,
         language: Language,
-        options: &Options,
+        options: &ScopedOptions,
+        version: Option<i64>,
     ) -> Arc<Snapshot> {
         let resolver = self.distro.resolver().await;
-        let document = Document::open(DocumentParams {
+        let (options, current_dir) = options.get(&uri);
+        let mut document = Document::open(DocumentParams {
             uri,
             text,
             language,
             resolver: &resolver,
             options,
-            current_dir: &self.current_dir,
+            current_dir,
         });
+        document.version = version;
 
-        let mut documents: Vec<Arc<Document>> = snapshot
-            .0
-            .iter()
-            .filter(|x| x.uri != document.uri)
-            .cloned()
-            .collect();
-
-        documents.push(Arc::new(document));
-        Arc::new(Snapshot(documents))
+        let mut snapshot = snapshot.clone();
+        snapshot.insert(Arc::new(document), options, current_dir);
+        Arc::new(snapshot)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::{LatexBuildOptions, LatexOptions};
+    use crate::{
+        protocol::{FolderOptions, LatexBuildOptions},
+        tex::UnknownDistribution,
+    };
     use itertools::Itertools;
     use std::env;
+    use tempfile::tempdir;
+
+    fn create_snapshot(
+        docs: Vec<Arc<Document>>,
+        options: &Options,
+        current_dir: &Path,
+    ) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        for doc in docs {
+            snapshot.insert(doc, options, current_dir);
+        }
+        snapshot
+    }
 
     fn create_simple_document(uri: &Uri, language: Language, text: &str) -> Arc<Document> {
         Arc::new(Document::open(DocumentParams {
@@ -580,13 +1058,16 @@ mod tests {
     fn relations_append_missing_extension() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar/baz.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#"\include{bar/baz}"#),
-            create_simple_document(&uri2, Language::Latex, r#""#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\include{bar/baz}"#),
+                create_simple_document(&uri2, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -598,13 +1079,16 @@ mod tests {
     fn relations_parent_directory() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar/baz.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#""#),
-            create_simple_document(&uri2, Language::Latex, r#"\input{../foo.tex}"#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#""#),
+                create_simple_document(&uri2, Language::Latex, r#"\input{../foo.tex}"#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -615,14 +1099,17 @@ mod tests {
     #[test]
     fn relations_invalid_include() {
         let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![create_simple_document(
-            &uri,
-            Language::Latex,
-            r#"\include{<foo>?|bar|:}"#,
-        )];
+        let snapshot = create_snapshot(
+            vec![create_simple_document(
+                &uri,
+                Language::Latex,
+                r#"\include{<foo>?|bar|:}"#,
+            )],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -634,13 +1121,16 @@ mod tests {
     fn relations_bibliography() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar.bib").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#"\addbibresource{bar.bib}"#),
-            create_simple_document(&uri2, Language::Bibtex, r#""#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\addbibresource{bar.bib}"#),
+                create_simple_document(&uri2, Language::Bibtex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri2, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri2)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -651,14 +1141,17 @@ mod tests {
     #[test]
     fn relations_unknown_include() {
         let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![create_simple_document(
-            &uri,
-            Language::Latex,
-            r#"\input{bar.tex}"#,
-        )];
+        let snapshot = create_snapshot(
+            vec![create_simple_document(
+                &uri,
+                Language::Latex,
+                r#"\input{bar.tex}"#,
+            )],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -670,13 +1163,16 @@ mod tests {
     fn relations_include_cycle() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#"\include{bar}"#),
-            create_simple_document(&uri2, Language::Latex, r#"\input{foo.tex}"#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\include{bar}"#),
+                create_simple_document(&uri2, Language::Latex, r#"\input{foo.tex}"#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -689,14 +1185,17 @@ mod tests {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar.tex").unwrap();
         let uri3 = Uri::parse("http://www.example.com/baz.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#"\input{bar.tex}\input{baz.tex}"#),
-            create_simple_document(&uri2, Language::Latex, r#""#),
-            create_simple_document(&uri3, Language::Latex, r#""#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\input{bar.tex}\input{baz.tex}"#),
+                create_simple_document(&uri2, Language::Latex, r#""#),
+                create_simple_document(&uri3, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri3, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri3)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -708,13 +1207,16 @@ mod tests {
     fn relations_aux_default_options() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/foo.aux").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#""#),
-            create_simple_document(&uri2, Language::Latex, r#""#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#""#),
+                create_simple_document(&uri2, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -738,27 +1240,30 @@ mod tests {
 
         let uri1 = Uri::from_file_path(cwd.join("foo.tex")).unwrap();
         let uri2 = Uri::from_file_path(cwd.join("build/foo.aux")).unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            Arc::new(Document::open(DocumentParams {
-                uri: uri1.clone(),
-                text: String::new(),
-                language: Language::Latex,
-                resolver: &Resolver::default(),
-                options: &options,
-                current_dir: &cwd,
-            })),
-            Arc::new(Document::open(DocumentParams {
-                uri: uri2.clone(),
-                text: String::new(),
-                language: Language::Latex,
-                resolver: &Resolver::default(),
-                options: &options,
-                current_dir: &cwd,
-            })),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                Arc::new(Document::open(DocumentParams {
+                    uri: uri1.clone(),
+                    text: String::new(),
+                    language: Language::Latex,
+                    resolver: &Resolver::default(),
+                    options: &options,
+                    current_dir: &cwd,
+                })),
+                Arc::new(Document::open(DocumentParams {
+                    uri: uri2.clone(),
+                    text: String::new(),
+                    language: Language::Latex,
+                    resolver: &Resolver::default(),
+                    options: &options,
+                    current_dir: &cwd,
+                })),
+            ],
+            &options,
+            &cwd,
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &options, &cwd)
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -779,27 +1284,30 @@ mod tests {
 
         let uri1 = Uri::from_file_path(cwd.join("src/foo.tex")).unwrap();
         let uri2 = Uri::from_file_path(cwd.join("foo.aux")).unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            Arc::new(Document::open(DocumentParams {
-                uri: uri1.clone(),
-                text: String::new(),
-                language: Language::Latex,
-                resolver: &Resolver::default(),
-                options: &options,
-                current_dir: &cwd,
-            })),
-            Arc::new(Document::open(DocumentParams {
-                uri: uri2.clone(),
-                text: String::new(),
-                language: Language::Latex,
-                resolver: &Resolver::default(),
-                options: &options,
-                current_dir: &cwd,
-            })),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                Arc::new(Document::open(DocumentParams {
+                    uri: uri1.clone(),
+                    text: String::new(),
+                    language: Language::Latex,
+                    resolver: &Resolver::default(),
+                    options: &options,
+                    current_dir: &cwd,
+                })),
+                Arc::new(Document::open(DocumentParams {
+                    uri: uri2.clone(),
+                    text: String::new(),
+                    language: Language::Latex,
+                    resolver: &Resolver::default(),
+                    options: &options,
+                    current_dir: &cwd,
+                })),
+            ],
+            &options,
+            &cwd,
+        );
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &options, &cwd)
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -812,15 +1320,18 @@ mod tests {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar/baz.tex").unwrap();
         let uri3 = Uri::parse("http://www.example.com/bar/qux/foo-bar.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#"\import{bar/}{baz.tex}"#),
-            create_simple_document(&uri2, Language::Latex, r#"\subimport{qux/}{foo-bar}"#),
-            create_simple_document(&uri3, Language::Latex, r#""#),
-        ];
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\import{bar/}{baz.tex}"#),
+                create_simple_document(&uri2, Language::Latex, r#"\subimport{qux/}{foo-bar}"#),
+                create_simple_document(&uri3, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
 
         let actual_uris: Vec<_> = snapshot
-            .relations(&uri1, &Options::default(), &env::current_dir().unwrap())
+            .relations(&uri1)
             .into_iter()
             .map(|doc| doc.uri.clone())
             .collect();
@@ -832,18 +1343,19 @@ mod tests {
     fn parent() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#""#),
-            create_simple_document(
-                &uri2,
-                Language::Latex,
-                r#"\begin{document}\include{foo}\end{document}"#,
-            ),
-        ];
-        let doc = snapshot
-            .parent(&uri1, &Options::default(), &env::current_dir().unwrap())
-            .unwrap();
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#""#),
+                create_simple_document(
+                    &uri2,
+                    Language::Latex,
+                    r#"\begin{document}\include{foo}\end{document}"#,
+                ),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let doc = snapshot.parent(&uri1).unwrap();
         assert_eq!(doc.uri, uri2);
     }
 
@@ -851,21 +1363,98 @@ mod tests {
     fn parent_nothing_found() {
         let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/bar.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(&uri1, Language::Latex, r#""#),
-            create_simple_document(&uri2, Language::Latex, r#"\begin{document}\end{document}"#),
-        ];
-        let doc = snapshot.parent(&uri1, &Options::default(), &env::current_dir().unwrap());
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#""#),
+                create_simple_document(&uri2, Language::Latex, r#"\begin{document}\end{document}"#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let doc = snapshot.parent(&uri1);
         assert_eq!(doc, None);
     }
 
+    #[test]
+    fn find_files() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in &[
+            "foo.tex",
+            "bar.bib",
+            "baz.sty",
+            "ignored.tex",
+            "build/qux.tex",
+        ] {
+            let path = dir.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        std::fs::write(dir.path().join(".gitignore"), "ignored.tex").unwrap();
+
+        let mut files = Workspace::find_files(dir.path(), &["build/".into()]);
+        files.sort();
+        assert_eq!(
+            files,
+            vec![dir.path().join("bar.bib"), dir.path().join("foo.tex")]
+        );
+    }
+
+    #[test]
+    fn orphans() {
+        let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
+        let uri2 = Uri::parse("http://www.example.com/bar.tex").unwrap();
+        let uri3 = Uri::parse("http://www.example.com/baz.tex").unwrap();
+        let mut snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\include{bar}"#),
+                create_simple_document(&uri2, Language::Latex, r#""#),
+                create_simple_document(&uri3, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        snapshot.set_open(&uri2, true);
+        assert_eq!(snapshot.orphans(), vec![uri3]);
+    }
+
+    #[test]
+    fn remove() {
+        let uri1 = Uri::parse("http://www.example.com/foo.tex").unwrap();
+        let uri2 = Uri::parse("http://www.example.com/bar.tex").unwrap();
+        let mut snapshot = create_snapshot(
+            vec![
+                create_simple_document(&uri1, Language::Latex, r#"\include{bar}"#),
+                create_simple_document(&uri2, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        snapshot.set_open(&uri2, true);
+        snapshot.remove(&uri2);
+        assert!(!snapshot.is_open(&uri2));
+        assert_eq!(snapshot.graph().children(&uri1), &[] as &[Uri]);
+        assert_eq!(
+            snapshot
+                .relations(&uri1)
+                .into_iter()
+                .map(|doc| doc.uri.clone())
+                .collect_vec(),
+            vec![uri1]
+        );
+    }
+
     #[test]
     fn expand_aux_file() {
         let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![create_simple_document(&uri, Language::Latex, r#""#)];
-        let expansion = snapshot.expand(&Options::default(), &env::current_dir().unwrap());
+        let snapshot = create_snapshot(
+            vec![create_simple_document(&uri, Language::Latex, r#""#)],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let expansion = snapshot.expand(&ScopedOptions::new(
+            Options::default(),
+            Arc::new(env::current_dir().unwrap()),
+        ));
         assert_eq!(
             expansion
                 .iter()
@@ -879,13 +1468,19 @@ mod tests {
     #[test]
     fn expand_local_package() {
         let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![create_simple_document(
-            &uri,
-            Language::Latex,
-            r#"\usepackage{foo-bar-baz}"#,
-        )];
-        let expansion = snapshot.expand(&Options::default(), &env::current_dir().unwrap());
+        let snapshot = create_snapshot(
+            vec![create_simple_document(
+                &uri,
+                Language::Latex,
+                r#"\usepackage{foo-bar-baz}"#,
+            )],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let expansion = snapshot.expand(&ScopedOptions::new(
+            Options::default(),
+            Arc::new(env::current_dir().unwrap()),
+        ));
 
         assert_eq!(
             expansion
@@ -900,13 +1495,19 @@ mod tests {
     #[test]
     fn expand_system_package() {
         let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![create_simple_document(
-            &uri,
-            Language::Latex,
-            r#"\usepackage{amsmath}"#,
-        )];
-        let expansion = snapshot.expand(&Options::default(), &env::current_dir().unwrap());
+        let snapshot = create_snapshot(
+            vec![create_simple_document(
+                &uri,
+                Language::Latex,
+                r#"\usepackage{amsmath}"#,
+            )],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let expansion = snapshot.expand(&ScopedOptions::new(
+            Options::default(),
+            Arc::new(env::current_dir().unwrap()),
+        ));
 
         assert_eq!(
             expansion
@@ -921,13 +1522,19 @@ mod tests {
     #[test]
     fn expand_subdirectory() {
         let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![create_simple_document(
-            &uri,
-            Language::Latex,
-            r#"\include{bar/baz}"#,
-        )];
-        let expansion = snapshot.expand(&Options::default(), &env::current_dir().unwrap());
+        let snapshot = create_snapshot(
+            vec![create_simple_document(
+                &uri,
+                Language::Latex,
+                r#"\include{bar/baz}"#,
+            )],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let expansion = snapshot.expand(&ScopedOptions::new(
+            Options::default(),
+            Arc::new(env::current_dir().unwrap()),
+        ));
         assert_eq!(
             expansion
                 .iter()
@@ -942,16 +1549,22 @@ mod tests {
     fn expand_import() {
         let uri1 = Uri::parse("http://www.example.com/qux/foo.tex").unwrap();
         let uri2 = Uri::parse("http://www.example.com/qux/baz/bar.tex").unwrap();
-        let mut snapshot = Snapshot::new();
-        snapshot.0 = vec![
-            create_simple_document(
-                &uri1,
-                Language::Latex,
-                r#"\import{.}{foo}\import{baz/}{bar}\import{baz/foo-bar/}{qux}"#,
-            ),
-            create_simple_document(&uri2, Language::Latex, r#""#),
-        ];
-        let expansion = snapshot.expand(&Options::default(), &env::current_dir().unwrap());
+        let snapshot = create_snapshot(
+            vec![
+                create_simple_document(
+                    &uri1,
+                    Language::Latex,
+                    r#"\import{.}{foo}\import{baz/}{bar}\import{baz/foo-bar/}{qux}"#,
+                ),
+                create_simple_document(&uri2, Language::Latex, r#""#),
+            ],
+            &Options::default(),
+            &env::current_dir().unwrap(),
+        );
+        let expansion = snapshot.expand(&ScopedOptions::new(
+            Options::default(),
+            Arc::new(env::current_dir().unwrap()),
+        ));
         assert_eq!(
             expansion
                 .iter()
@@ -961,4 +1574,145 @@ mod tests {
             vec!["http://www.example.com/qux/baz/foo-bar/qux.tex"]
         )
     }
+
+    #[tokio::test]
+    async fn update_stale_version() {
+        let workspace = Workspace::new(Arc::new(UnknownDistribution::default()), None);
+        let options = ScopedOptions::new(Options::default(), Arc::new(env::current_dir().unwrap()));
+        let uri = Uri::parse("http://www.example.com/foo.tex").unwrap();
+        let document = TextDocumentItem::new((*uri).clone(), "latex".into(), 2, "foo".into());
+        workspace.add(document, &options).await;
+
+        workspace
+            .update(uri.clone(), Some(1), "bar".into(), &options)
+            .await;
+        let snapshot = workspace.get().await;
+        let doc = snapshot.find(&uri).unwrap();
+        assert_eq!(doc.text, "foo");
+        assert_eq!(doc.version, Some(2));
+
+        workspace
+            .update(uri.clone(), Some(3), "baz".into(), &options)
+            .await;
+        let snapshot = workspace.get().await;
+        let doc = snapshot.find(&uri).unwrap();
+        assert_eq!(doc.text, "baz");
+        assert_eq!(doc.version, Some(3));
+    }
+
+    #[tokio::test]
+    async fn collect_garbage_memory_limit() {
+        let dir = tempdir().unwrap();
+        let path1 = dir.path().join("foo.tex");
+        let path2 = dir.path().join("bar.tex");
+        std::fs::write(&path1, "foo").unwrap();
+        std::fs::write(&path2, "bar").unwrap();
+        let uri1 = Uri::from_file_path(&path1).unwrap();
+        let uri3 = Uri::from_file_path(dir.path().join("baz.tex")).unwrap();
+
+        let indexed_options = |memory_limit_mb| {
+            let options = Options {
+                latex: Some(LatexOptions {
+                    indexing: Some(LatexIndexingOptions {
+                        on_startup: Some(true),
+                        exclude: None,
+                    }),
+                    memory_limit_mb,
+                    ..LatexOptions::default()
+                }),
+                ..Options::default()
+            };
+            ScopedOptions {
+                default: options.clone(),
+                current_dir: Arc::new(dir.path().to_owned()),
+                folders: vec![FolderOptions {
+                    uri: Uri::from_file_path(dir.path()).unwrap(),
+                    path: dir.path().to_owned(),
+                    options: Some(options),
+                }],
+            }
+        };
+
+        let workspace = Workspace::new(Arc::new(UnknownDistribution::default()), None);
+        let options = indexed_options(None);
+        workspace.load(&path1, &options).await.unwrap();
+        workspace.load(&path2, &options).await.unwrap();
+        let document = TextDocumentItem::new((*uri3).clone(), "latex".into(), 1, "baz".into());
+        workspace.add(document, &options).await;
+
+        assert!(workspace.collect_garbage(&options).await.is_empty());
+        assert_eq!(workspace.get().await.documents().len(), 3);
+
+        let unused_uris = workspace.collect_garbage(&indexed_options(Some(0))).await;
+        assert_eq!(unused_uris.len(), 2);
+        assert!(unused_uris.contains(&uri1));
+        let snapshot = workspace.get().await;
+        assert_eq!(snapshot.documents().len(), 1);
+        assert!(snapshot.find(&uri3).is_some());
+    }
+
+    #[tokio::test]
+    async fn collect_garbage_folder_memory_limit() {
+        let dir = tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("foo")).unwrap();
+        let path = dir.path().join("foo").join("foo.tex");
+        std::fs::write(&path, "foo").unwrap();
+        let uri1 = Uri::from_file_path(&path).unwrap();
+        let uri2 = Uri::from_file_path(dir.path().join("bar.tex")).unwrap();
+
+        let folder_options = |memory_limit_mb| ScopedOptions {
+            default: Options::default(),
+            current_dir: Arc::new(dir.path().to_owned()),
+            folders: vec![FolderOptions {
+                uri: Uri::from_file_path(dir.path().join("foo")).unwrap(),
+                path: dir.path().join("foo"),
+                options: Some(Options {
+                    latex: Some(LatexOptions {
+                        indexing: Some(LatexIndexingOptions {
+                            on_startup: Some(true),
+                            exclude: None,
+                        }),
+                        memory_limit_mb,
+                        ..LatexOptions::default()
+                    }),
+                    ..Options::default()
+                }),
+            }],
+        };
+
+        let workspace = Workspace::new(Arc::new(UnknownDistribution::default()), None);
+        let options = folder_options(None);
+        workspace.load(&path, &options).await.unwrap();
+        let document = TextDocumentItem::new((*uri2).clone(), "latex".into(), 1, "bar".into());
+        workspace.add(document, &options).await;
+        assert!(workspace.collect_garbage(&options).await.is_empty());
+
+        let unused_uris = workspace.collect_garbage(&folder_options(Some(0))).await;
+        assert_eq!(unused_uris, vec![uri1]);
+        let snapshot = workspace.get().await;
+        assert_eq!(snapshot.documents().len(), 1);
+        assert!(snapshot.find(&uri2).is_some());
+    }
+
+    #[tokio::test]
+    async fn load_all_keeps_open_documents() {
+        let dir = tempdir().unwrap();
+        let path1 = dir.path().join("foo.tex");
+        let path2 = dir.path().join("bar.bib");
+        std::fs::write(&path1, "foo").unwrap();
+        std::fs::write(&path2, "@article{bar,}").unwrap();
+        let uri1 = Uri::from_file_path(&path1).unwrap();
+        let uri2 = Uri::from_file_path(&path2).unwrap();
+        let options = ScopedOptions::new(Options::default(), Arc::new(dir.path().to_owned()));
+
+        let workspace = Workspace::new(Arc::new(UnknownDistribution::default()), None);
+        let document = TextDocumentItem::new((*uri1).clone(), "latex".into(), 1, "baz".into());
+        workspace.add(document, &options).await;
+        workspace.load_all(&[path1, path2], &options).await;
+
+        let snapshot = workspace.get().await;
+        assert_eq!(snapshot.documents().len(), 2);
+        assert_eq!(snapshot.find(&uri1).unwrap().text, "baz");
+        assert!(snapshot.find(&uri2).unwrap().content.as_bibtex().is_some());
+    }
 }
@@ -0,0 +1,121 @@
+use texlab::{
+    protocol::CleanResult,
+    test::{TestBedBuilder, PULL_CAPABILITIES},
+};
+
+#[tokio::test]
+async fn auxiliary() {
+    let mut test_bed = TestBedBuilder::new()
+        .file("main.tex", "")
+        .file("main.aux", "")
+        .file("main.log", "")
+        .file("main.pdf", "")
+        .build()
+        .await;
+
+    test_bed.spawn();
+    test_bed.initialize(PULL_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+
+    let result = test_bed
+        .execute_command("texlab.cleanAuxiliary", "main.tex")
+        .await
+        .unwrap();
+
+    test_bed.shutdown().await;
+
+    let actual_result: CleanResult = serde_json::from_value(result).unwrap();
+    let expected_result = CleanResult {
+        deleted_files: vec![
+            test_bed.uri("main.aux").into(),
+            test_bed.uri("main.log").into(),
+        ],
+    };
+    assert_eq!(actual_result, expected_result);
+    assert!(!test_bed.path("main.aux").exists());
+    assert!(test_bed.path("main.pdf").exists());
+}
+
+#[tokio::test]
+async fn artifacts() {
+    let mut test_bed = TestBedBuilder::new()
+        .file("main.tex", "")
+        .file("main.aux", "")
+        .file("main.pdf", "")
+        .build()
+        .await;
+
+    test_bed.spawn();
+    test_bed.initialize(PULL_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+
+    let result = test_bed
+        .execute_command("texlab.cleanArtifacts", "main.tex")
+        .await
+        .unwrap();
+
+    test_bed.shutdown().await;
+
+    let actual_result: CleanResult = serde_json::from_value(result).unwrap();
+    let expected_result = CleanResult {
+        deleted_files: vec![
+            test_bed.uri("main.aux").into(),
+            test_bed.uri("main.pdf").into(),
+        ],
+    };
+    assert_eq!(actual_result, expected_result);
+    assert!(!test_bed.path("main.pdf").exists());
+}
+
+#[tokio::test]
+async fn auxiliary_included() {
+    let mut test_bed = TestBedBuilder::new()
+        .file(
+            "main.tex",
+            "\\begin{document}\\include{chapters/one}\\input{chapters/two}\\end{document}",
+        )
+        .file("main.aux", "")
+        .file("chapters/one.tex", "")
+        .file("chapters/one.aux", "")
+        .file("chapters/two.tex", "")
+        .file("chapters/two.aux", "")
+        .build()
+        .await;
+
+    test_bed.spawn();
+    test_bed.initialize(PULL_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+    test_bed.open("chapters/one.tex").await;
+    test_bed.open("chapters/two.tex").await;
+
+    let result = test_bed
+        .execute_command("texlab.cleanAuxiliary", "chapters/one.tex")
+        .await
+        .unwrap();
+
+    test_bed.shutdown().await;
+
+    let actual_result: CleanResult = serde_json::from_value(result).unwrap();
+    let expected_result = CleanResult {
+        deleted_files: vec![
+            test_bed.uri("main.aux").into(),
+            test_bed.uri("chapters/one.aux").into(),
+        ],
+    };
+    assert_eq!(actual_result, expected_result);
+    assert!(test_bed.path("chapters/two.aux").exists());
+}
+
+#[tokio::test]
+async fn unknown_command() {
+    let mut test_bed = TestBedBuilder::new().file("main.tex", "").build().await;
+    test_bed.spawn();
+    test_bed.initialize(PULL_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+
+    let result = test_bed.execute_command("texlab.foo", "main.tex").await;
+
+    test_bed.shutdown().await;
+
+    assert_eq!(result, None);
+}
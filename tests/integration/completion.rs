@@ -397,6 +397,39 @@ async fn latex_citation_invalid() {
     assert_eq!(actual_item.documentation, None);
 }
 
+#[tokio::test]
+async fn latex_citation_many_files() {
+    let entries: String = (0..60)
+        .map(|i| format!("@article{{foo{}, title = {{Baz}}}}\n", i))
+        .collect();
+    let mut test_bed = TestBedBuilder::new()
+        .file(
+            "main.tex",
+            indoc!(
+                r#"
+                    \addbibresource{a.bib}
+                    \addbibresource{b.bib}
+                    \cite{gra}
+                "#
+            ),
+        )
+        .file("a.bib", &entries)
+        .file("b.bib", "@article{bar, title = {Graphs}}")
+        .build()
+        .await;
+    test_bed.spawn();
+    test_bed.initialize(PULL_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+    test_bed.open("a.bib").await;
+    test_bed.open("b.bib").await;
+
+    let actual_labels = run_list(&test_bed, "main.tex", 2, 9).await;
+
+    test_bed.shutdown().await;
+
+    assert_eq!(actual_labels, vec!["bar"]);
+}
+
 #[tokio::test]
 async fn latex_color_name() {
     let mut test_bed = TestBedBuilder::new()
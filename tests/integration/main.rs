@@ -1,3 +1,4 @@
+mod clean;
 mod completion;
 mod definition;
 mod document_symbol;
@@ -9,4 +10,5 @@ mod link;
 mod prepare_rename;
 mod reference;
 mod rename;
+mod watched_files;
 mod workspace_symbol;
@@ -0,0 +1,113 @@
+use indoc::indoc;
+use std::{fs, time::Duration};
+use texlab::{
+    protocol::FileChangeType,
+    test::{TestBed, TestBedBuilder, WATCH_CAPABILITIES},
+};
+
+async fn run_labels(test_bed: &TestBed) -> Vec<String> {
+    test_bed
+        .completion("main.tex", 1, 5)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|item| item.label)
+        .collect()
+}
+
+#[tokio::test]
+async fn created() {
+    let mut test_bed = TestBedBuilder::new()
+        .file(
+            "main.tex",
+            indoc!(
+                r#"
+                    \include{foo}
+                    \ref{}
+                "#
+            ),
+        )
+        .build()
+        .await;
+    test_bed.spawn();
+    test_bed.initialize(WATCH_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+    assert!(!run_labels(&test_bed).await.contains(&"sec:foo".into()));
+
+    fs::write(test_bed.path("foo.tex"), r#"\section{Foo}\label{sec:foo}"#).unwrap();
+    test_bed
+        .change_file("foo.tex", FileChangeType::Created)
+        .await;
+    let actual_labels = run_labels(&test_bed).await;
+
+    test_bed.shutdown().await;
+
+    assert!(actual_labels.contains(&"sec:foo".into()));
+}
+
+#[tokio::test]
+async fn deleted() {
+    let mut test_bed = TestBedBuilder::new()
+        .file(
+            "main.tex",
+            indoc!(
+                r#"
+                    \include{foo}
+                    \ref{}
+                "#
+            ),
+        )
+        .file("foo.tex", r#"\section{Foo}\label{sec:foo}"#)
+        .build()
+        .await;
+    test_bed.spawn();
+    test_bed.initialize(WATCH_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+    test_bed
+        .change_file("foo.tex", FileChangeType::Created)
+        .await;
+    assert!(run_labels(&test_bed).await.contains(&"sec:foo".into()));
+
+    fs::remove_file(test_bed.path("foo.tex")).unwrap();
+    test_bed
+        .change_file("foo.tex", FileChangeType::Deleted)
+        .await;
+    let actual_labels = run_labels(&test_bed).await;
+
+    test_bed.shutdown().await;
+
+    assert!(!actual_labels.contains(&"sec:foo".into()));
+}
+
+#[tokio::test]
+async fn changed_outside_of_folders() {
+    let mut test_bed = TestBedBuilder::new()
+        .file(
+            "main.tex",
+            indoc!(
+                r#"
+                    \include{foo}
+                    \ref{}
+                "#
+            ),
+        )
+        .file("foo.tex", r#"\section{Foo}\label{sec:foo}"#)
+        .build()
+        .await;
+    test_bed.spawn();
+    test_bed.initialize(WATCH_CAPABILITIES.clone()).await;
+    test_bed.open("main.tex").await;
+    test_bed
+        .change_file("foo.tex", FileChangeType::Created)
+        .await;
+    assert!(run_labels(&test_bed).await.contains(&"sec:foo".into()));
+
+    // The file system clock may lag behind the time at which the document was loaded.
+    tokio::time::delay_for(Duration::from_millis(50)).await;
+    fs::write(test_bed.path("foo.tex"), r#"\section{Bar}\label{sec:bar}"#).unwrap();
+    let actual_labels = run_labels(&test_bed).await;
+
+    test_bed.shutdown().await;
+
+    assert!(actual_labels.contains(&"sec:bar".into()));
+}